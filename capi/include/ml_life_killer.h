@@ -0,0 +1,124 @@
+/*
+ * C interface for running trained networks, built from the capi crate as libml_life_killer_c.
+ *
+ * All functions are safe to call from multiple threads as long as a single network isn't used by two threads at once.
+ * Functions returning an MlkStatus other than MLK_OK set a message retrievable with mlk_last_error on the same thread.
+ */
+
+#ifndef ML_LIFE_KILLER_H
+#define ML_LIFE_KILLER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum MlkStatus {
+    MLK_OK = 0,
+    /* The network doesn't want to change any tile, not an error as such. */
+    MLK_NO_MOVE = 1,
+    MLK_NULL_POINTER = 2,
+    MLK_INVALID_ARGUMENT = 3,
+    MLK_IO_ERROR = 4,
+    MLK_INVALID_SAVE = 5,
+    /* Something went wrong inside of the library, the network should not be used anymore. */
+    MLK_INTERNAL_ERROR = 6,
+} MlkStatus;
+
+typedef enum MlkTopology {
+    MLK_TOPOLOGY_BOUNDED = 0,
+    MLK_TOPOLOGY_TOROIDAL = 1,
+} MlkTopology;
+
+/* A network loaded from a save, along with the player config it was trained with. */
+typedef struct MlkNetwork MlkNetwork;
+
+typedef struct MlkKernelOutput {
+    /* The score by which selecting the position should be preferred. */
+    float score;
+    /* How much the network wants the tile to be alive, below -0.5 meaning dead and above 0.5 alive. */
+    float state;
+} MlkKernelOutput;
+
+typedef struct MlkMove {
+    size_t x;
+    size_t y;
+    /* 1 if the tile should be made alive, 0 if it should be killed. */
+    uint8_t alive;
+} MlkMove;
+
+/* Loads a network save file written by the trainer. The network must be freed with mlk_network_free. */
+MlkStatus mlk_network_load_path(const char *path, MlkNetwork **out_network);
+
+/* Loads a network save from the contents of a save file. The network must be freed with mlk_network_free. */
+MlkStatus mlk_network_load_buffer(const uint8_t *data, size_t length, MlkNetwork **out_network);
+
+/* Frees a network, passing NULL does nothing. */
+void mlk_network_free(MlkNetwork *network);
+
+/* The width and height of the kernels the network sees, always odd. */
+size_t mlk_network_kernel_diameter(const MlkNetwork *network);
+
+/* Whether the network expects tile ages in its kernels. */
+uint8_t mlk_network_uses_tile_ages(const MlkNetwork *network);
+
+/* Makes a network with memory or history forget the boards it has picked moves on, e.g. before starting a new game. */
+void mlk_network_reset_memory(MlkNetwork *network);
+
+/* Tells a network how many of the rounds of the game are left, for networks which take it into account. */
+void mlk_network_set_rounds_left(MlkNetwork *network, size_t rounds_left, size_t max_rounds);
+
+/*
+ * Sets the rule the game is played with, which networks using previews tick the board with. Conway's by default.
+ *
+ * Bit n of each mask stands for n live neighbours, e.g. 1 << 3 births and (1 << 2) | (1 << 3) survivals for Conway's.
+ * Returns MLK_INVALID_ARGUMENT if any bit above 8 is set.
+ */
+MlkStatus mlk_network_set_rule(MlkNetwork *network, uint16_t birth_mask, uint16_t survive_mask);
+
+/*
+ * Scores a single kernel centered on the tile being considered.
+ *
+ * tiles holds kernel_diameter * kernel_diameter values, column by column (x outer, y inner),
+ * with 1 for alive, 0 for dead and -1 for tiles outside of the board.
+ * ages holds the number of generations each tile has been in its current state in the same order,
+ * it's required if the network uses tile ages and ignored otherwise.
+ * Kernels are scored as if the network didn't remember anything yet.
+ * Nor is there a game around them, so networks using context features (including the rounds left), previews,
+ * neighbour counts or history are fed zeros for those inputs, unlike when picking moves with mlk_network_pick_move.
+ */
+MlkStatus mlk_network_score_kernel(
+    MlkNetwork *network,
+    const int8_t *tiles,
+    const uint32_t *ages,
+    size_t tile_count,
+    MlkKernelOutput *out_output);
+
+/*
+ * Picks the move the network wants to make on a board, without applying it.
+ *
+ * tiles holds width * height values row by row, with any non-zero value meaning alive.
+ * Networks using tile ages see every tile as freshly changed, since a single board doesn't have a history.
+ * Networks with memory or history remember the boards they picked moves on until mlk_network_reset_memory is called,
+ * so the boards of a game should be passed in order. The history also starts over when the board size changes.
+ * Networks using previews tick the board with the rule set with mlk_network_set_rule.
+ * Returns MLK_NO_MOVE if the network doesn't want to change anything.
+ */
+MlkStatus mlk_network_pick_move(
+    MlkNetwork *network,
+    const uint8_t *tiles,
+    size_t width,
+    size_t height,
+    MlkTopology topology,
+    MlkMove *out_move);
+
+/* The message of the last error on the calling thread, valid until the next call into the library. Never NULL. */
+const char *mlk_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ML_LIFE_KILLER_H */
@@ -0,0 +1,432 @@
+//! C interface for running trained networks, see include/ml_life_killer.h for the documentation of each function.
+//! Every entry point catches panics and reports them as an internal error instead of unwinding into C.
+
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString, c_char, c_int},
+    panic::{self, AssertUnwindSafe},
+    slice,
+};
+
+use libgame::{
+    Game,
+    board::{GameBoard, TileState},
+    rule::Rule,
+    topology::Topology,
+};
+use libml::game::{
+    NetworkPlayer,
+    kernel::{Kernel, KernelContext},
+    networksave::NetworkSave,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MlkStatus {
+    Ok = 0,
+    NoMove = 1,
+    NullPointer = 2,
+    InvalidArgument = 3,
+    IoError = 4,
+    InvalidSave = 5,
+    InternalError = 6,
+}
+
+/// The values of the MlkTopology enum, which is passed as a plain int since C doesn't guarantee valid enum values.
+const MLK_TOPOLOGY_BOUNDED: c_int = 0;
+const MLK_TOPOLOGY_TOROIDAL: c_int = 1;
+
+pub struct MlkNetwork {
+    player: NetworkPlayer<'static>,
+
+    /// The rule games are ticked with for previews, Conway's unless set with mlk_network_set_rule.
+    rule: Rule,
+}
+
+/// The highest neighbour count a rule can be given for, as bit n of the C masks stands for n live neighbours.
+const MAX_NEIGHBOUR_COUNT: usize = 8;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct MlkKernelOutput {
+    pub score: f32,
+    pub state: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct MlkMove {
+    pub x: usize,
+    pub y: usize,
+    pub alive: u8,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+struct Error {
+    status: MlkStatus,
+    message: String,
+}
+
+impl Error {
+    fn new(status: MlkStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn null_pointer(argument: &str) -> Self {
+        Self::new(MlkStatus::NullPointer, format!("{} is null", argument))
+    }
+}
+
+/// Runs the body of an entry point, turning errors and panics into a status code and the last error message.
+fn run(body: impl FnOnce() -> Result<MlkStatus, Error>) -> MlkStatus {
+    let result = panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|panic_payload| {
+        let panic_message = panic_payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic_payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Unknown panic".to_owned());
+
+        Err(Error::new(MlkStatus::InternalError, panic_message))
+    });
+
+    match result {
+        Ok(status) => status,
+        Err(error) => {
+            set_last_error(&error.message);
+            error.status
+        }
+    }
+}
+
+fn set_last_error(message: &str) {
+    // Interior nul bytes can't be represented in a C string, so just drop them.
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = message);
+}
+
+fn load(save_serialized: &[u8], out_network: *mut *mut MlkNetwork) -> Result<MlkStatus, Error> {
+    // NOTE: Migration warnings are dropped, as there's nothing to report them through besides errors.
+    let (save, _warnings) = NetworkSave::from_slice(save_serialized)
+        .map_err(|e| Error::new(MlkStatus::InvalidSave, format!("{e:#}")))?;
+
+    let network = Box::new(MlkNetwork {
+        player: NetworkPlayer::new(save.player_config, save.network),
+        rule: Rule::default(),
+    });
+
+    // SAFETY: The caller guarantees that out_network is valid for writes, and it's been checked for null.
+    unsafe { *out_network = Box::into_raw(network) };
+
+    Ok(MlkStatus::Ok)
+}
+
+/// # Safety
+/// `path` must be a valid nul-terminated string and `out_network` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mlk_network_load_path(
+    path: *const c_char,
+    out_network: *mut *mut MlkNetwork,
+) -> MlkStatus {
+    run(|| {
+        if path.is_null() {
+            return Err(Error::null_pointer("path"));
+        }
+        if out_network.is_null() {
+            return Err(Error::null_pointer("out_network"));
+        }
+
+        // SAFETY: The caller guarantees that the path is a valid C string.
+        let path = unsafe { CStr::from_ptr(path) }
+            .to_str()
+            .map_err(|_| Error::new(MlkStatus::InvalidArgument, "path isn't valid UTF-8"))?;
+
+        let save_serialized = std::fs::read(path).map_err(|e| {
+            Error::new(
+                MlkStatus::IoError,
+                format!("Couldn't read network save: {}", e),
+            )
+        })?;
+
+        load(&save_serialized, out_network)
+    })
+}
+
+/// # Safety
+/// `data` must be valid for reads of `length` bytes and `out_network` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mlk_network_load_buffer(
+    data: *const u8,
+    length: usize,
+    out_network: *mut *mut MlkNetwork,
+) -> MlkStatus {
+    run(|| {
+        if data.is_null() {
+            return Err(Error::null_pointer("data"));
+        }
+        if out_network.is_null() {
+            return Err(Error::null_pointer("out_network"));
+        }
+
+        // SAFETY: The caller guarantees that the buffer is valid for the given length.
+        let save_serialized = unsafe { slice::from_raw_parts(data, length) };
+
+        load(save_serialized, out_network)
+    })
+}
+
+/// # Safety
+/// `network` must be null or a network returned by one of the load functions which hasn't been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mlk_network_free(network: *mut MlkNetwork) {
+    if !network.is_null() {
+        // SAFETY: The caller guarantees that the network came from Box::into_raw and is only freed once.
+        drop(unsafe { Box::from_raw(network) });
+    }
+}
+
+/// # Safety
+/// `network` must be null or a valid network.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mlk_network_kernel_diameter(network: *const MlkNetwork) -> usize {
+    // SAFETY: The caller guarantees that the network is valid if it isn't null.
+    unsafe { network.as_ref() }.map_or(0, |network| network.player.config.kernel_diameter)
+}
+
+/// # Safety
+/// `network` must be null or a valid network.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mlk_network_uses_tile_ages(network: *const MlkNetwork) -> u8 {
+    // SAFETY: The caller guarantees that the network is valid if it isn't null.
+    unsafe { network.as_ref() }.map_or(0, |network| network.player.config.use_tile_ages as u8)
+}
+
+/// # Safety
+/// `network` must be null or a valid network not used by any other thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mlk_network_reset_memory(network: *mut MlkNetwork) {
+    // SAFETY: The caller guarantees that the network is valid and not aliased if it isn't null.
+    if let Some(network) = unsafe { network.as_mut() } {
+        network.player.reset_memory();
+    }
+}
+
+/// # Safety
+/// `network` must be null or a valid network not used by any other thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mlk_network_set_rounds_left(
+    network: *mut MlkNetwork,
+    rounds_left: usize,
+    max_rounds: usize,
+) {
+    // SAFETY: The caller guarantees that the network is valid and not aliased if it isn't null.
+    if let Some(network) = unsafe { network.as_mut() } {
+        network.player.set_rounds_left(rounds_left, max_rounds);
+    }
+}
+
+/// # Safety
+/// `network` must be a valid network not used by any other thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mlk_network_set_rule(
+    network: *mut MlkNetwork,
+    birth_mask: u16,
+    survive_mask: u16,
+) -> MlkStatus {
+    run(|| {
+        // SAFETY: The caller guarantees that the network is valid and not aliased if it isn't null.
+        let network = unsafe { network.as_mut() }.ok_or_else(|| Error::null_pointer("network"))?;
+
+        if (birth_mask | survive_mask) >> (MAX_NEIGHBOUR_COUNT + 1) != 0 {
+            return Err(Error::new(
+                MlkStatus::InvalidArgument,
+                format!("Rule masks can only have bits 0 to {MAX_NEIGHBOUR_COUNT} set"),
+            ));
+        }
+
+        let neighbour_counts = |mask: u16| {
+            (0..=MAX_NEIGHBOUR_COUNT)
+                .filter(|count| mask & (1 << count) != 0)
+                .collect()
+        };
+
+        network.rule = Rule {
+            birth: neighbour_counts(birth_mask),
+            survive: neighbour_counts(survive_mask),
+            noise: None,
+        };
+
+        Ok(MlkStatus::Ok)
+    })
+}
+
+/// # Safety
+/// `network` must be a valid network not used by any other thread, `tiles` (and `ages` if not null)
+/// must be valid for reads of `tile_count` values and `out_output` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mlk_network_score_kernel(
+    network: *mut MlkNetwork,
+    tiles: *const i8,
+    ages: *const u32,
+    tile_count: usize,
+    out_output: *mut MlkKernelOutput,
+) -> MlkStatus {
+    run(|| {
+        // SAFETY: The caller guarantees that the network is valid and not aliased if it isn't null.
+        let network = unsafe { network.as_mut() }.ok_or_else(|| Error::null_pointer("network"))?;
+        if tiles.is_null() {
+            return Err(Error::null_pointer("tiles"));
+        }
+        if out_output.is_null() {
+            return Err(Error::null_pointer("out_output"));
+        }
+
+        let player_config = network.player.config;
+        let expected_tile_count = player_config.kernel_diameter.pow(2);
+        if tile_count != expected_tile_count {
+            return Err(Error::new(
+                MlkStatus::InvalidArgument,
+                format!(
+                    "Expected {} kernel tiles, got {}",
+                    expected_tile_count, tile_count
+                ),
+            ));
+        }
+
+        // SAFETY: The caller guarantees that the tiles are valid for the given count.
+        let tiles = unsafe { slice::from_raw_parts(tiles, tile_count) }
+            .iter()
+            .map(|tile| match tile {
+                1 => Ok(Some(TileState::Alive)),
+                0 => Ok(Some(TileState::Dead)),
+                -1 => Ok(None),
+                _ => Err(Error::new(
+                    MlkStatus::InvalidArgument,
+                    format!("Invalid kernel tile value {}", tile),
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+
+        let ages = if player_config.use_tile_ages {
+            if ages.is_null() {
+                return Err(Error::null_pointer("ages"));
+            }
+
+            // SAFETY: The caller guarantees that the ages are valid for the given count if they aren't null.
+            let ages = unsafe { slice::from_raw_parts(ages, tile_count) };
+            Some(ages.iter().map(|age| *age as usize).collect())
+        } else {
+            None
+        };
+
+        // Single kernels are scored without memory, as there are no earlier moves for them to remember.
+        // Nor do they have a game around them for context features.
+        let output = network.player.compute_kernel(Kernel {
+            tiles,
+            ages,
+            memory: None,
+            context: KernelContext::default(),
+            preview: None,
+            neighbour_counts: None,
+            history: None,
+        });
+
+        // SAFETY: The caller guarantees that out_output is valid for writes, and it's been checked for null.
+        unsafe {
+            *out_output = MlkKernelOutput {
+                score: output.score,
+                state: output.state,
+            }
+        };
+
+        Ok(MlkStatus::Ok)
+    })
+}
+
+/// # Safety
+/// `network` must be a valid network not used by any other thread, `tiles` must be valid for reads of
+/// `width * height` bytes and `out_move` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mlk_network_pick_move(
+    network: *mut MlkNetwork,
+    tiles: *const u8,
+    width: usize,
+    height: usize,
+    topology: c_int,
+    out_move: *mut MlkMove,
+) -> MlkStatus {
+    run(|| {
+        // SAFETY: The caller guarantees that the network is valid and not aliased if it isn't null.
+        let network = unsafe { network.as_mut() }.ok_or_else(|| Error::null_pointer("network"))?;
+        if tiles.is_null() {
+            return Err(Error::null_pointer("tiles"));
+        }
+        if out_move.is_null() {
+            return Err(Error::null_pointer("out_move"));
+        }
+
+        let topology = match topology {
+            MLK_TOPOLOGY_BOUNDED => Topology::Bounded,
+            MLK_TOPOLOGY_TOROIDAL => Topology::Toroidal,
+            _ => {
+                return Err(Error::new(
+                    MlkStatus::InvalidArgument,
+                    format!("Invalid topology {}", topology),
+                ));
+            }
+        };
+
+        let tile_count = width
+            .checked_mul(height)
+            .filter(|tile_count| *tile_count > 0)
+            .ok_or_else(|| Error::new(MlkStatus::InvalidArgument, "Invalid board size"))?;
+
+        // SAFETY: The caller guarantees that the tiles are valid for the given size.
+        let tiles = unsafe { slice::from_raw_parts(tiles, tile_count) }
+            .iter()
+            .map(|tile| {
+                if *tile != 0 {
+                    TileState::Alive
+                } else {
+                    TileState::Dead
+                }
+            })
+            .collect();
+
+        let mut board = GameBoard::with_tiles(width, height, tiles);
+        if network.player.config.use_tile_ages {
+            board.track_ages();
+        }
+
+        let mut game = Game::new(board, network.rule.clone());
+        game.topology = topology;
+
+        // The game is new for every call, but the memory and history of the boards seen before live in the player.
+        let Some(network_move) = network.player.choose_move(&game) else {
+            return Ok(MlkStatus::NoMove);
+        };
+
+        // SAFETY: The caller guarantees that out_move is valid for writes, and it's been checked for null.
+        unsafe {
+            *out_move = MlkMove {
+                x: network_move.position.x,
+                y: network_move.position.y,
+                alive: (network_move.new_state == TileState::Alive) as u8,
+            }
+        };
+
+        Ok(MlkStatus::Ok)
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn mlk_last_error() -> *const c_char {
+    LAST_ERROR
+        .try_with(|last_error| last_error.borrow().as_ptr())
+        .unwrap_or(c"".as_ptr())
+}
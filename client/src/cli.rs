@@ -3,8 +3,11 @@ use std::{
 };
 
 use anyhow::{anyhow, bail, Context};
-use libgame::board::{GameBoard, TileState};
-use libml::game::networksave::NetworkSave;
+use libgame::{
+    board::{GameBoard, TileState},
+    topology::Topology,
+};
+use libml::game::networksave::{MigrationOptions, NetworkSave};
 
 use crate::{
     ticker::{ml::MLTicker, nature::NatureTicker, Ticker, TickerGenerator, TickerHost},
@@ -50,13 +53,27 @@ where
                     Box::new(generator)
                 }
                 Some("network") => {
-                    let network_save_path =
-                        args.next().context("No network path provided")?.to_owned();
-
-                    let generator = || {
-                        let network_save = NetworkSave::load(network_save_path)
-                            .expect("Couldn't load network save");
-
+                    let network_save_path = args.next().context("No network path provided")?;
+
+                    // Older saves don't store the functions of the network, so allow telling them.
+                    let mut migration_options = MigrationOptions::default();
+                    let assumed_config = &mut migration_options.assumed_network_config;
+                    if let Some(activator) = args.next() {
+                        assumed_config.activator = activator.parse().context("Unknown activator")?;
+                    }
+                    if let Some(combinator) = args.next() {
+                        assumed_config.combinator = combinator.parse().context("Unknown combinator")?;
+                    }
+
+                    // Load the save here instead of in the ticker, so that invalid saves are reported instead of crashing.
+                    let (network_save, warnings) =
+                        NetworkSave::load_with_options(network_save_path, &migration_options)?;
+
+                    for warning in warnings {
+                        eprintln!("Warning: {}", warning);
+                    }
+
+                    let generator = move || {
                         let NetworkSave {
                             player_config,
                             network,
@@ -99,6 +116,7 @@ where
             for tile in &mut state.game.board.tiles {
                 *tile = TileState::Dead;
             }
+            state.board_generation += 1;
         }
 
         "resize" => {
@@ -112,6 +130,7 @@ where
             board.tiles.resize(width * height, TileState::Dead);
             board.width = width;
             board.height = height;
+            state.board_generation += 1;
         }
 
         "random" => {
@@ -129,6 +148,28 @@ where
             let board = &mut state.game.board;
 
             *board = GameBoard::new_random(board.width, board.height, alive_count, block_size);
+            state.board_generation += 1;
+        }
+
+        "ages" => {
+            let mut state = state_arc.write().unwrap();
+            let board = &mut state.game.board;
+
+            match args.next() {
+                Some("on") | None => board.track_ages(),
+                Some("off") => board.ages = None,
+                Some(other) => bail!("Unknown age tracking mode '{}'", other),
+            }
+        }
+
+        "topology" => {
+            let topology = match args.next().context("Missing topology")? {
+                "bounded" => Topology::Bounded,
+                "toroidal" => Topology::Toroidal,
+                other => bail!("Unknown topology '{}'", other),
+            };
+
+            state_arc.write().unwrap().game.topology = topology;
         }
 
         "setrate" => {
@@ -15,6 +15,10 @@ mod ticker;
 
 pub struct State {
     game: Game,
+
+    /// Counts how often the board has been replaced, so that tickers remembering earlier boards know to forget them.
+    board_generation: usize,
+
     tickers: HashMap<String, TickerHost>,
 }
 
@@ -23,6 +27,7 @@ fn main() {
 
     let state_arc = Arc::new(RwLock::new(State {
         game,
+        board_generation: 0,
         tickers: HashMap::new(),
     }));
 
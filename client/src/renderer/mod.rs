@@ -51,15 +51,28 @@ fn draw(state: &mut RendererState, mut frame: RenderFrame) {
 
     const HALF_TILE_MARGIN: u32 = 1;
 
+    // The age at which tiles reach their final color.
+    const AGE_COLOR_GENERATIONS: usize = 32;
+
     frame.fill([10, 10, 10, 255]);
 
     for (tile_pos, tile) in global_state.game.board.enumerate_tiles() {
         let tile_screen_x = tile_pos.x as u32 * tile_width;
         let tile_screen_y = tile_pos.y as u32 * tile_height;
 
-        let color = match tile {
-            TileState::Alive => [255; 4],
-            TileState::Dead => [0, 0, 0, 255],
+        let color = match (tile, global_state.game.board.tile_age(tile_pos)) {
+            (TileState::Alive, None) => [255; 4],
+            (TileState::Alive, Some(age)) => {
+                // Fresh cells are white and slowly turn blue as they settle into still lifes.
+                let fade = (age.min(AGE_COLOR_GENERATIONS) * 200 / AGE_COLOR_GENERATIONS) as u8;
+                [255 - fade, 255 - fade / 2, 255, 255]
+            }
+            (TileState::Dead, Some(age)) if age < AGE_COLOR_GENERATIONS / 4 => {
+                // Leave a short afterglow of recently died cells.
+                let glow = (40 - age * 160 / AGE_COLOR_GENERATIONS) as u8;
+                [glow, glow, glow, 255]
+            }
+            (TileState::Dead, _) => [0, 0, 0, 255],
         };
 
         frame.draw_square(
@@ -2,41 +2,35 @@ use libml::{
     game::{NetworkPlayer, NetworkPlayerConfig},
     network::Network,
 };
-use ouroboros::self_referencing;
 
 use crate::State;
 
 use super::Ticker;
 
 pub struct MLTicker {
-    network_and_player: NetworkAndPlayer,
+    network_player: NetworkPlayer<'static>,
+
+    /// The board generation the player's memory is of, see `State::board_generation`.
+    board_generation: Option<usize>,
 }
 
 impl MLTicker {
     pub fn new(network: Network, config: NetworkPlayerConfig) -> Self {
-        let network_and_player = NetworkAndPlayerBuilder {
-            network,
-            player_builder: |network| NetworkPlayer::new(config, network),
+        Self {
+            network_player: NetworkPlayer::new(config, network),
+            board_generation: None,
         }
-        .build();
-
-        Self { network_and_player }
     }
 }
 
 impl Ticker for MLTicker {
     fn tick(&mut self, state: &mut State) {
-        self.network_and_player.with_player_mut(|network_player| {
-            network_player.play_step(&mut state.game);
-        });
-    }
-}
-
-#[self_referencing]
-struct NetworkAndPlayer {
-    network: Network,
+        // What the player remembers is of a board which isn't there anymore.
+        if self.board_generation != Some(state.board_generation) {
+            self.network_player.reset_memory();
+            self.board_generation = Some(state.board_generation);
+        }
 
-    #[borrows(mut network)]
-    #[not_covariant] // It isn't safe to use NetworkPlayer with a lifetime smaller than 'this.
-    pub player: NetworkPlayer<'this>,
+        self.network_player.play_step(&mut state.game);
+    }
 }
@@ -0,0 +1,161 @@
+//! Serves the game environment over stdin/stdout, one JSON request per line and one JSON response per line,
+//! so that agents written in other languages can play the same game with the same scoring as the trainer.
+//!
+//! Usage: `envserver [config path]`, where the config is either a trainer config or just its adapter config.
+//!
+//! Requests:
+//! - `{"cmd": "reset", "seed": 42}` starts a new game, the seed being optional.
+//! - `{"cmd": "step", "action": {"position": {"x": 1, "y": 2}, "new_state": "dead"}}` plays a round,
+//!   a null or missing action skips the turn.
+//! - `{"cmd": "observe"}` returns the current observation without playing.
+//! - `{"cmd": "render_ascii"}` returns the board drawn with 'O' for alive and '.' for dead tiles.
+//!
+//! Responses always contain an "ok" field, and an "error" field if it's false.
+
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+};
+
+use anyhow::Context;
+use libgame::board::{GameBoard, TileState};
+use libml::game::environment::{Action, Environment, EnvironmentConfig};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Request {
+    Reset {
+        #[serde(default)]
+        seed: Option<u64>,
+    },
+    Step {
+        #[serde(default)]
+        action: Action,
+    },
+    Observe,
+    RenderAscii,
+}
+
+fn main() -> anyhow::Result<()> {
+    let config = match env::args().nth(1) {
+        Some(config_path) => load_config(&config_path)?,
+        None => default_config(),
+    };
+
+    let mut environment = Environment::new(config).context("Invalid environment config")?;
+    environment.reset(0);
+
+    let mut stdout = io::stdout().lock();
+
+    for line_res in io::stdin().lock().lines() {
+        let line = line_res.context("Couldn't read request")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match handle_request(&mut environment, &line) {
+            Ok(mut response) => {
+                response["ok"] = json!(true);
+                response
+            }
+            Err(e) => json!({ "ok": false, "error": format!("{e:#}") }),
+        };
+
+        serde_json::to_writer(&mut stdout, &response).context("Couldn't write response")?;
+        writeln!(stdout).context("Couldn't write response")?;
+        stdout.flush().context("Couldn't flush response")?;
+    }
+
+    Ok(())
+}
+
+fn handle_request(environment: &mut Environment, line: &str) -> anyhow::Result<Value> {
+    let request: Request = serde_json::from_str(line).context("Invalid request")?;
+
+    let response = match request {
+        Request::Reset { seed } => {
+            environment.reset(seed.unwrap_or_else(rand::random));
+
+            json!({
+                "observation": environment.observe(),
+                "baseline_reward": environment.baseline_reward(),
+            })
+        }
+
+        Request::Step { action } => {
+            let step = environment.step(action);
+
+            let mut response = json!({
+                "observation": step.observation,
+                "reward": step.reward,
+                "done": step.done,
+                "info": step.info,
+            });
+
+            // NOTE: Added afterwards, since the step borrows the environment for its observation.
+            response["score"] = json!(environment.score());
+            response
+        }
+
+        Request::Observe => json!({
+            "observation": environment.observe(),
+            "done": environment.is_done(),
+            "score": environment.score(),
+        }),
+
+        Request::RenderAscii => json!({
+            "ascii": render_ascii(&environment.game().board),
+        }),
+    };
+
+    Ok(response)
+}
+
+fn load_config(path: &str) -> anyhow::Result<EnvironmentConfig> {
+    let config_serialized = fs::read(path).context("Couldn't read config")?;
+    let config: Value =
+        serde_json::from_slice(&config_serialized).context("Couldn't deserialize config")?;
+
+    // Accept whole trainer configs as well, so that the same file can be used for both.
+    let environment_config = match config.get("adapter_config") {
+        Some(adapter_config) => adapter_config.clone(),
+        None => config,
+    };
+
+    serde_json::from_value(environment_config).context("Invalid environment config")
+}
+
+fn default_config() -> EnvironmentConfig {
+    EnvironmentConfig {
+        width: 16,
+        height: 16,
+        alive_cells: 128,
+        block_size: 1,
+        max_rounds: 128,
+        disable_nature: false,
+        evil: true,
+        rule: Default::default(),
+        rule_map: None,
+        topology: Default::default(),
+        track_ages: false,
+    }
+}
+
+fn render_ascii(board: &GameBoard) -> String {
+    let mut ascii = String::with_capacity((board.width + 1) * board.height);
+
+    for y in 0..board.height {
+        for x in 0..board.width {
+            ascii.push(match board.tile([x, y]) {
+                Some(TileState::Alive) => 'O',
+                _ => '.',
+            });
+        }
+
+        ascii.push('\n');
+    }
+
+    ascii
+}
@@ -0,0 +1,78 @@
+use super::board::TileState;
+
+#[cfg(feature = "serde")]
+use super::encoding;
+
+/// Tracks how many generations each tile has been in its current state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        into = "encoding::SerializedAgeLayer",
+        try_from = "encoding::SerializedAgeLayer"
+    )
+)]
+pub struct AgeLayer {
+    pub(crate) ages: Vec<TileAge>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub(crate) struct TileAge {
+    /// The state the tile had when its age was last updated.
+    pub state: TileState,
+    pub generations: usize,
+}
+
+impl AgeLayer {
+    pub fn new(tiles: &[TileState]) -> Self {
+        let ages = tiles
+            .iter()
+            .map(|tile| TileAge {
+                state: *tile,
+                generations: 0,
+            })
+            .collect();
+
+        Self { ages }
+    }
+
+    /// The age of the tile at the given index, assuming it's currently in the given state.
+    /// Tiles that have been changed outside of a tick (e.g. by a player) are considered to be zero generations old.
+    pub fn age(&self, index: usize, current_state: TileState) -> Option<usize> {
+        let tile_age = self.ages.get(index)?;
+
+        if tile_age.state == current_state {
+            Some(tile_age.generations)
+        } else {
+            Some(0)
+        }
+    }
+
+    /// Advances the ages by a single generation, given the tiles before and after the generation.
+    pub fn update(&mut self, previous_tiles: &[TileState], next_tiles: &[TileState]) {
+        // The board may have been resized since the last update, in which case the old ages are meaningless.
+        if self.ages.len() != previous_tiles.len() || self.ages.len() != next_tiles.len() {
+            *self = Self::new(next_tiles);
+            return;
+        }
+
+        let tiles = previous_tiles.iter().zip(next_tiles);
+
+        for (tile_age, (previous_tile, next_tile)) in self.ages.iter_mut().zip(tiles) {
+            let generations = if previous_tile != next_tile {
+                0
+            } else if tile_age.state == *previous_tile {
+                tile_age.generations + 1
+            } else {
+                // The tile was changed outside of a tick and has stayed that way for this generation.
+                1
+            };
+
+            *tile_age = TileAge {
+                state: *next_tile,
+                generations,
+            };
+        }
+    }
+}
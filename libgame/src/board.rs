@@ -1,12 +1,31 @@
 use itertools::Itertools;
+use rand::Rng;
 
-use super::pos::Position;
+use super::{
+    age::AgeLayer,
+    pos::{Offset, Position},
+    topology::Topology,
+};
+
+#[cfg(feature = "serde")]
+use super::encoding;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        into = "encoding::SerializedGameBoard",
+        try_from = "encoding::SerializedGameBoard"
+    )
+)]
 pub struct GameBoard {
     pub width: usize,
     pub height: usize,
     pub tiles: Vec<TileState>,
+
+    /// Optional per-tile age tracking, updated by the game on each tick.
+    pub ages: Option<AgeLayer>,
 }
 
 impl GameBoard {
@@ -16,6 +35,19 @@ impl GameBoard {
     }
 
     pub fn new_random(width: usize, height: usize, alive_cells: usize, block_size: usize) -> Self {
+        Self::new_random_with_rng(width, height, alive_cells, block_size, &mut rand::rng())
+    }
+
+    pub fn new_random_with_rng<R>(
+        width: usize,
+        height: usize,
+        alive_cells: usize,
+        block_size: usize,
+        rng: &mut R,
+    ) -> Self
+    where
+        R: Rng,
+    {
         let mut board = Self::new(width, height);
 
         let mut available_board_positions = (0..=board.width - block_size)
@@ -29,7 +61,7 @@ impl GameBoard {
                     panic!("Board size too small for requested alive cell count");
                 }
 
-                let chosen_position_index = rand::random_range(0..available_board_positions.len());
+                let chosen_position_index = rng.random_range(0..available_board_positions.len());
 
                 // FIXME: Only the root position of blocks are removed from available cells.
                 //        This whole block thing is really hackily implemented here overall, may want to recode.
@@ -57,9 +89,27 @@ impl GameBoard {
             width,
             height,
             tiles,
+            ages: None,
         }
     }
 
+    /// Starts tracking the ages of tiles, if not already tracked. All tiles start out zero generations old.
+    pub fn track_ages(&mut self) {
+        if self.ages.is_none() {
+            self.ages = Some(AgeLayer::new(&self.tiles));
+        }
+    }
+
+    /// The number of generations the tile has been in its current state, if ages are tracked.
+    pub fn tile_age<P>(&self, pos: P) -> Option<usize>
+    where
+        P: Into<Position>,
+    {
+        let index = self.pos_to_index(pos)?;
+        let tile = self.tiles.get(index)?;
+        self.ages.as_ref()?.age(index, *tile)
+    }
+
     pub fn tile<P>(&self, pos: P) -> Option<&TileState>
     where
         P: Into<Position>,
@@ -76,6 +126,35 @@ impl GameBoard {
         self.tiles.get_mut(index)
     }
 
+    /// Offsets a position according to the given topology, returning None if the result is outside of the board.
+    pub fn offset_position(
+        &self,
+        pos: Position,
+        offset: Offset,
+        topology: Topology,
+    ) -> Option<Position> {
+        topology.offset_position(pos, offset, self.width, self.height)
+    }
+
+    /// Iterates over the square neighbourhood of the given radius around a position, including the center itself.
+    /// The offsets are iterated column by column, i.e. the y offset changes fastest.
+    /// Positions outside of the board are given as None.
+    pub fn neighbourhood(
+        &self,
+        center: Position,
+        radius: usize,
+        topology: Topology,
+    ) -> impl Iterator<Item = (Offset, Option<Position>)> + '_ {
+        let radius = radius as isize;
+
+        (-radius..=radius)
+            .cartesian_product(-radius..=radius)
+            .map(move |(x, y)| {
+                let offset = Offset { x, y };
+                (offset, self.offset_position(center, offset, topology))
+            })
+    }
+
     pub fn enumerate_tiles(&self) -> impl Iterator<Item = (Position, &TileState)> {
         self.tiles
             .iter()
@@ -100,7 +179,7 @@ impl GameBoard {
         Some(x + (y * self.width))
     }
 
-    fn index_to_pos(&self, index: usize) -> Position {
+    pub fn index_to_pos(&self, index: usize) -> Position {
         let y = index / self.width;
         let x = index % self.width;
         Position { x, y }
@@ -108,6 +187,8 @@ impl GameBoard {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum TileState {
     Alive,
 
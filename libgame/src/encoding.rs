@@ -0,0 +1,219 @@
+//! Compact serialized forms of the game types, so that boards aren't serialized as one enum per tile.
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    age::{AgeLayer, TileAge},
+    board::{GameBoard, TileState},
+};
+
+const ALIVE_SYMBOL: char = 'o';
+const DEAD_SYMBOL: char = 'b';
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SerializedGameBoard {
+    width: usize,
+    height: usize,
+
+    /// The tiles run-length encoded row by row, with 'o' for alive and 'b' for dead tiles.
+    /// A run is written as an optional count followed by the symbol, i.e. "3b2o" is three dead and two alive tiles.
+    tiles: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ages: Option<AgeLayer>,
+}
+
+impl From<GameBoard> for SerializedGameBoard {
+    fn from(board: GameBoard) -> Self {
+        Self {
+            width: board.width,
+            height: board.height,
+            tiles: encode_tiles(&board.tiles),
+            ages: board.ages,
+        }
+    }
+}
+
+impl TryFrom<SerializedGameBoard> for GameBoard {
+    type Error = String;
+
+    fn try_from(serialized: SerializedGameBoard) -> Result<Self, Self::Error> {
+        let tile_count = serialized
+            .width
+            .checked_mul(serialized.height)
+            .ok_or("Board size overflows")?;
+
+        let tiles = decode_tiles(&serialized.tiles, tile_count)?;
+
+        if tiles.len() != tile_count {
+            return Err(format!(
+                "Expected {} tiles for a {}x{} board, got {}",
+                tile_count,
+                serialized.width,
+                serialized.height,
+                tiles.len()
+            ));
+        }
+
+        let mut board = GameBoard::with_tiles(serialized.width, serialized.height, tiles);
+        board.ages = serialized.ages;
+        Ok(board)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SerializedAgeLayer {
+    /// The states the ages were recorded for, encoded the same way as the tiles of a board.
+    states: String,
+    generations: Vec<usize>,
+}
+
+impl From<AgeLayer> for SerializedAgeLayer {
+    fn from(age_layer: AgeLayer) -> Self {
+        let states: Vec<_> = age_layer.ages.iter().map(|age| age.state).collect();
+
+        Self {
+            states: encode_tiles(&states),
+            generations: age_layer.ages.iter().map(|age| age.generations).collect(),
+        }
+    }
+}
+
+impl TryFrom<SerializedAgeLayer> for AgeLayer {
+    type Error = String;
+
+    fn try_from(serialized: SerializedAgeLayer) -> Result<Self, Self::Error> {
+        let states = decode_tiles(&serialized.states, serialized.generations.len())?;
+
+        if states.len() != serialized.generations.len() {
+            return Err(format!(
+                "Expected {} age states, got {}",
+                serialized.generations.len(),
+                states.len()
+            ));
+        }
+
+        let ages = states
+            .into_iter()
+            .zip(serialized.generations)
+            .map(|(state, generations)| TileAge { state, generations })
+            .collect();
+
+        Ok(Self { ages })
+    }
+}
+
+fn encode_tiles(tiles: &[TileState]) -> String {
+    let mut encoded = String::new();
+    let mut tile_iter = tiles.iter().peekable();
+
+    while let Some(tile) = tile_iter.next() {
+        let mut run_length = 1;
+        while tile_iter.next_if_eq(&tile).is_some() {
+            run_length += 1;
+        }
+
+        if run_length > 1 {
+            encoded.push_str(&run_length.to_string());
+        }
+
+        encoded.push(match tile {
+            TileState::Alive => ALIVE_SYMBOL,
+            TileState::Dead => DEAD_SYMBOL,
+        });
+    }
+
+    encoded
+}
+
+fn decode_tiles(encoded: &str, max_tile_count: usize) -> Result<Vec<TileState>, String> {
+    let mut tiles = Vec::new();
+    let mut run_length: Option<usize> = None;
+
+    for symbol in encoded.chars() {
+        if let Some(digit) = symbol.to_digit(10) {
+            let next_run_length = run_length
+                .unwrap_or(0)
+                .checked_mul(10)
+                .and_then(|run_length| run_length.checked_add(digit as usize))
+                .ok_or("Run length overflows")?;
+
+            run_length = Some(next_run_length);
+            continue;
+        }
+
+        let tile = match symbol {
+            ALIVE_SYMBOL => TileState::Alive,
+            DEAD_SYMBOL => TileState::Dead,
+            _ => return Err(format!("Unknown tile symbol '{}'", symbol)),
+        };
+
+        let run_length = run_length.take().unwrap_or(1);
+        if tiles.len().saturating_add(run_length) > max_tile_count {
+            return Err(format!("More than the expected {} tiles", max_tile_count));
+        }
+
+        tiles.extend(std::iter::repeat_n(tile, run_length));
+    }
+
+    if run_length.is_some() {
+        return Err("Run length without a tile symbol at the end of the tiles".to_owned());
+    }
+
+    Ok(tiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(board: GameBoard) {
+        let serialized = SerializedGameBoard::from(board.clone());
+        let deserialized = GameBoard::try_from(serialized).unwrap();
+
+        assert_eq!(deserialized.width, board.width);
+        assert_eq!(deserialized.height, board.height);
+        assert_eq!(deserialized.tiles, board.tiles);
+    }
+
+    fn decode(width: usize, height: usize, tiles: &str) -> Result<GameBoard, String> {
+        GameBoard::try_from(SerializedGameBoard {
+            width,
+            height,
+            tiles: tiles.to_owned(),
+            ages: None,
+        })
+    }
+
+    #[test]
+    fn boards_round_trip() {
+        // A full alive row, runs of every length including ones with several digits, and single tiles.
+        let mut board = GameBoard::new(12, 4);
+        for (index, tile) in board.tiles.iter_mut().enumerate() {
+            if index < 12 || (14..17).contains(&index) || index == 20 || index == 47 {
+                *tile = TileState::Alive;
+            }
+        }
+        assert_eq!(encode_tiles(&board.tiles), "12o2b3o3bo26bo");
+        round_trip(board);
+
+        round_trip(GameBoard::new(5, 3));
+        round_trip(GameBoard::new(0, 0));
+    }
+
+    #[test]
+    fn bad_runs_are_rejected() {
+        assert!(decode(4, 4, "15o").is_err(), "Too few tiles");
+        assert!(decode(4, 4, "12o3b").is_err(), "Too few tiles");
+        assert!(decode(4, 4, "17o").is_err(), "Too many tiles");
+        assert!(decode(4, 4, "16ob").is_err(), "Too many tiles");
+        assert!(decode(4, 4, "16").is_err(), "Run without a symbol");
+        assert!(
+            decode(4, 4, "99999999999999999999999o").is_err(),
+            "Overflowing run"
+        );
+        assert!(decode(4, 4, "16x").is_err(), "Unknown symbol");
+
+        assert!(decode(4, 4, "16b").is_ok());
+    }
+}
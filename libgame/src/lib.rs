@@ -1,30 +1,131 @@
+use std::mem;
+
 use board::{GameBoard, TileState};
-use pos::Position;
+use pos::{Offset, Position};
+use rand::{rngs::StdRng, SeedableRng};
 use rule::Rule;
+use rulemap::{RuleMap, RuleMapError};
+use topology::Topology;
 
+pub mod age;
 pub mod board;
+#[cfg(feature = "serde")]
+mod encoding;
 pub mod pos;
 pub mod rule;
+pub mod rulemap;
+pub mod topology;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedGame"))]
 pub struct Game {
     pub board: GameBoard,
     pub rule: Rule,
+
+    /// Optional rules for specific regions of the board, which has to be the same size as the board when set.
+    /// Tiles outside of the map, i.e. if the board is resized afterwards, follow the main rule.
+    rule_map: Option<RuleMap>,
+
+    /// How the edges of the board behave.
+    pub topology: Topology,
+
+    /// The source of randomness for noisy rules. Cloning the game also clones the RNG state,
+    /// so that a cloned game will progress exactly the same way as the original.
+    /// Deserialized games get a freshly seeded RNG.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub rng: StdRng,
+
+    /// The tiles of the previous generation, reused as the buffer the next generation is written to
+    /// so that ticking doesn't need to allocate.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    back_buffer: Vec<TileState>,
 }
 
 impl Game {
     pub fn new(board: GameBoard, rule: Rule) -> Self {
-        Self { board, rule }
+        Self::with_rng(board, rule, new_entropy_rng())
+    }
+
+    pub fn with_seed(board: GameBoard, rule: Rule, seed: u64) -> Self {
+        Self::with_rng(board, rule, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(board: GameBoard, rule: Rule, rng: StdRng) -> Self {
+        let back_buffer = vec![TileState::default(); board.tiles.len()];
+
+        Self {
+            board,
+            rule,
+            rule_map: None,
+            topology: Topology::default(),
+            rng,
+            back_buffer,
+        }
+    }
+
+    pub fn rule_map(&self) -> Option<&RuleMap> {
+        self.rule_map.as_ref()
+    }
+
+    /// Sets or clears the rule map, failing if the map isn't the same size as the board.
+    pub fn set_rule_map(&mut self, rule_map: Option<RuleMap>) -> Result<(), RuleMapError> {
+        if let Some(rule_map) = &rule_map {
+            rule_map.check_size(self.board.width, self.board.height)?;
+        }
+
+        self.rule_map = rule_map;
+        Ok(())
     }
 
     pub fn tick(&mut self) {
-        let next_tiles = self
-            .board
-            .enumerate_tiles()
-            .map(|(tile_pos, tile)| self.tick_tile(tile_pos, tile))
-            .collect();
+        let Self {
+            board,
+            rule,
+            rule_map,
+            topology,
+            rng,
+            back_buffer,
+        } = self;
 
-        self.board = GameBoard::with_tiles(self.board.width, self.board.height, next_tiles);
+        // This only allocates if the board has been resized since the last tick.
+        back_buffer.resize(board.tiles.len(), TileState::default());
+
+        for y in 0..board.height {
+            // The alive cell counts of the columns left of, at and right of the current tile,
+            // which are shifted along as we go so that every column is only counted once per row.
+            let row_start = Position { x: 0, y };
+            let mut left_column = Self::count_alive_in_column(board, *topology, row_start, -1);
+            let mut center_column = Self::count_alive_in_column(board, *topology, row_start, 0);
+
+            for x in 0..board.width {
+                let tile_pos = Position { x, y };
+                let right_column = Self::count_alive_in_column(board, *topology, tile_pos, 1);
+                let index = x + y * board.width;
+
+                let tile = board.tiles[index];
+                let alive_neighbor_count = left_column + center_column + right_column
+                    - (tile == TileState::Alive) as usize;
+
+                let tile_rule = Self::rule_at(rule, rule_map, tile_pos);
+                let mut next_tile = Self::tick_tile(tile_rule, tile, alive_neighbor_count);
+
+                if let Some(noise) = &tile_rule.noise {
+                    next_tile = noise.apply(rng, tile, next_tile);
+                }
+
+                back_buffer[index] = next_tile;
+
+                left_column = center_column;
+                center_column = right_column;
+            }
+        }
+
+        if let Some(ages) = &mut board.ages {
+            ages.update(&board.tiles, back_buffer);
+        }
+
+        mem::swap(&mut board.tiles, back_buffer);
     }
 
     pub fn count_cells(&self, variant: TileState) -> usize {
@@ -35,16 +136,26 @@ impl Game {
             .count()
     }
 
-    fn tick_tile(&self, tile_pos: Position, tile: &TileState) -> TileState {
-        let alive_neighbor_count = self
-            .tile_neighbors(tile_pos)
-            .into_iter()
-            .filter(|neighbor| **neighbor == TileState::Alive)
-            .count();
+    /// The rule the given tile follows.
+    pub fn rule<P>(&self, pos: P) -> &Rule
+    where
+        P: Into<Position>,
+    {
+        Self::rule_at(&self.rule, &self.rule_map, pos.into())
+    }
+
+    // NOTE: Takes the fields separately so that the RNG can be borrowed mutably at the same time.
+    fn rule_at<'a>(rule: &'a Rule, rule_map: &'a Option<RuleMap>, pos: Position) -> &'a Rule {
+        rule_map
+            .as_ref()
+            .and_then(|rule_map| rule_map.rule(pos))
+            .unwrap_or(rule)
+    }
 
+    fn tick_tile(rule: &Rule, tile: TileState, alive_neighbor_count: usize) -> TileState {
         let alive = match tile {
-            TileState::Alive => self.rule.survive.contains(&alive_neighbor_count),
-            TileState::Dead => self.rule.birth.contains(&alive_neighbor_count),
+            TileState::Alive => rule.survive.contains(&alive_neighbor_count),
+            TileState::Dead => rule.birth.contains(&alive_neighbor_count),
         };
 
         if alive {
@@ -54,38 +165,147 @@ impl Game {
         }
     }
 
-    fn tile_neighbors(&self, tile_pos: Position) -> Vec<&TileState> {
-        const NEIGHBOR_RELATIVE_POSITIONS: &'static [[isize; 2]] = &[
-            [-1, -1],
-            [-1, 0],
-            [-1, 1],
-            [0, -1],
-            [0, 1],
-            [1, -1],
-            [1, 0],
-            [1, 1],
-        ];
-
-        fn abs_pos(center_pos: usize, offset_pos: isize) -> Option<usize> {
-            let abs_pos = center_pos as isize + offset_pos;
-
-            if abs_pos < 0 {
-                None
-            } else {
-                Some(abs_pos as usize)
-            }
-        }
+    /// Counts the alive cells in the column of three tiles centered on the given column offset from the position.
+    /// Tiles outside of the board count as dead.
+    // NOTE: This deliberately doesn't go through GameBoard::neighbourhood, which visits the whole square around
+    //       a tile. The tick only counts the one new column entering the window at each step and slides the
+    //       other two along, so it needs a single column, and it goes through the same offset_position
+    //       that neighbourhood uses, so both agree on the topology.
+    fn count_alive_in_column(
+        board: &GameBoard,
+        topology: Topology,
+        pos: Position,
+        column_offset: isize,
+    ) -> usize {
+        (-1..=1)
+            .filter_map(|y| {
+                board.offset_position(
+                    pos,
+                    Offset {
+                        x: column_offset,
+                        y,
+                    },
+                    topology,
+                )
+            })
+            .filter(|tile_pos| board.tile(*tile_pos) == Some(&TileState::Alive))
+            .count()
+    }
+}
 
-        NEIGHBOR_RELATIVE_POSITIONS
-            .iter()
-            .filter_map(|rel_pos| {
-                let pos = Position {
-                    x: abs_pos(tile_pos.x, rel_pos[0])?,
-                    y: abs_pos(tile_pos.y, rel_pos[1])?,
-                };
+/// Game as it's deserialized, before the rule map has been checked against the board.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedGame {
+    board: GameBoard,
+    rule: Rule,
+    rule_map: Option<RuleMap>,
+
+    #[serde(default)]
+    topology: Topology,
+}
 
-                self.board.tile(pos)
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedGame> for Game {
+    type Error = RuleMapError;
+
+    fn try_from(unchecked: UncheckedGame) -> Result<Self, Self::Error> {
+        let mut game = Self::new(unchecked.board, unchecked.rule);
+        game.set_rule_map(unchecked.rule_map)?;
+        game.topology = unchecked.topology;
+        Ok(game)
+    }
+}
+
+fn new_entropy_rng() -> StdRng {
+    StdRng::from_rng(&mut rand::rng())
+}
+
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        // The back buffer is only scratch space, so it's left out of the comparison.
+        self.board == other.board
+            && self.rule == other.rule
+            && self.rule_map == other.rule_map
+            && self.topology == other.topology
+            && self.rng == other.rng
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    /// Ticks the board by counting the eight neighbours of every tile separately.
+    fn reference_tick(game: &Game) -> Vec<TileState> {
+        let board = &game.board;
+        let (width, height) = (board.width as isize, board.height as isize);
+
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let alive_neighbor_count = (-1..=1)
+                    .flat_map(|offset_y| (-1..=1).map(move |offset_x| (offset_x, offset_y)))
+                    .filter(|offset| *offset != (0, 0))
+                    .filter_map(|(offset_x, offset_y)| {
+                        let (neighbor_x, neighbor_y) = (x + offset_x, y + offset_y);
+
+                        match game.topology {
+                            Topology::Bounded => ((0..width).contains(&neighbor_x)
+                                && (0..height).contains(&neighbor_y))
+                            .then_some((neighbor_x, neighbor_y)),
+                            Topology::Toroidal => {
+                                Some((neighbor_x.rem_euclid(width), neighbor_y.rem_euclid(height)))
+                            }
+                        }
+                    })
+                    .filter(|(neighbor_x, neighbor_y)| {
+                        board.tiles[(neighbor_x + neighbor_y * width) as usize] == TileState::Alive
+                    })
+                    .count();
+
+                Game::tick_tile(
+                    &game.rule,
+                    board.tiles[(x + y * width) as usize],
+                    alive_neighbor_count,
+                )
             })
             .collect()
     }
+
+    #[test]
+    fn tick_matches_reference() {
+        let rng = &mut StdRng::seed_from_u64(0);
+
+        for topology in [Topology::Bounded, Topology::Toroidal] {
+            for _ in 0..100 {
+                let width = rng.random_range(1..=12);
+                let height = rng.random_range(1..=12);
+                let alive_cells = rng.random_range(0..=width * height);
+                let board = GameBoard::new_random_with_rng(width, height, alive_cells, 1, rng);
+
+                let rule = if rng.random_bool(0.5) {
+                    Rule::default()
+                } else {
+                    Rule::high_life()
+                };
+
+                let mut game = Game::new(board, rule);
+                game.topology = topology;
+
+                for _ in 0..10 {
+                    let expected_tiles = reference_tick(&game);
+                    game.tick();
+
+                    assert_eq!(
+                        game.board.tiles, expected_tiles,
+                        "{}x{} board with {:?} topology",
+                        width, height, topology
+                    );
+                }
+            }
+        }
+    }
 }
@@ -1,6 +1,7 @@
 use std::ops::Add;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub x: usize,
     pub y: usize,
@@ -31,3 +32,43 @@ impl Add for Position {
         }
     }
 }
+
+impl Position {
+    /// Offsets the position, returning None if either coordinate would go below zero or overflow.
+    pub fn checked_add_offset(self, offset: Offset) -> Option<Self> {
+        Some(Self {
+            x: self.x.checked_add_signed(offset.x)?,
+            y: self.y.checked_add_signed(offset.y)?,
+        })
+    }
+
+    /// Offsets the position, wrapping the coordinates around to stay within the given (non-zero) bounds.
+    pub fn wrapping_add_offset(self, offset: Offset, width: usize, height: usize) -> Self {
+        fn wrap(coordinate: usize, offset: isize, size: usize) -> usize {
+            // Any usize and isize fit into an i128, so the sum can't overflow.
+            (coordinate as i128 + offset as i128).rem_euclid(size as i128) as usize
+        }
+
+        Self {
+            x: wrap(self.x, offset.x, width),
+            y: wrap(self.y, offset.y, height),
+        }
+    }
+}
+
+/// A signed relative position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Offset {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl From<[isize; 2]> for Offset {
+    fn from(value: [isize; 2]) -> Self {
+        Self {
+            x: value[0],
+            y: value[1],
+        }
+    }
+}
@@ -1,7 +1,18 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
+use std::{error::Error, fmt};
+
+use rand::Rng;
+
+use super::board::TileState;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rule {
     pub birth: Vec<usize>,
     pub survive: Vec<usize>,
+
+    /// Optional randomness applied on top of the otherwise deterministic rule.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub noise: Option<RuleNoise>,
 }
 
 impl Default for Rule {
@@ -9,6 +20,181 @@ impl Default for Rule {
         Self {
             birth: vec![3],
             survive: vec![2, 3],
+            noise: None,
+        }
+    }
+}
+
+impl Rule {
+    /// The HighLife rule (B36/S23), which behaves much like the default rule but also has a replicator.
+    pub fn high_life() -> Self {
+        Self {
+            birth: vec![3, 6],
+            survive: vec![2, 3],
+            noise: None,
+        }
+    }
+}
+
+/// The probabilities can only be set through `RuleNoise::new`, which makes sure that they're between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedRuleNoise"))]
+pub struct RuleNoise {
+    /// The probability of a birth called for by the rule actually happening.
+    birth_probability: f64,
+
+    /// The probability of a cell surviving when the rule says it should.
+    survive_probability: f64,
+
+    /// The probability of a dead cell coming to life on its own, regardless of its neighbors.
+    spontaneous_birth_probability: f64,
+
+    /// The probability of any tile flipping its state after all other rules have been applied.
+    flip_probability: f64,
+}
+
+impl Default for RuleNoise {
+    fn default() -> Self {
+        Self {
+            birth_probability: 1.0,
+            survive_probability: 1.0,
+            spontaneous_birth_probability: 0.0,
+            flip_probability: 0.0,
         }
     }
 }
+
+impl RuleNoise {
+    /// Creates the noise, failing if any of the probabilities isn't between 0 and 1.
+    pub fn new(
+        birth_probability: f64,
+        survive_probability: f64,
+        spontaneous_birth_probability: f64,
+        flip_probability: f64,
+    ) -> Result<Self, InvalidProbabilityError> {
+        let probabilities = [
+            ("birth_probability", birth_probability),
+            ("survive_probability", survive_probability),
+            ("spontaneous_birth_probability", spontaneous_birth_probability),
+            ("flip_probability", flip_probability),
+        ];
+
+        // NOTE: Also catches NaN, which isn't contained in any range.
+        if let Some((name, probability)) = probabilities
+            .into_iter()
+            .find(|(_, probability)| !(0.0..=1.0).contains(probability))
+        {
+            return Err(InvalidProbabilityError { name, probability });
+        }
+
+        Ok(Self {
+            birth_probability,
+            survive_probability,
+            spontaneous_birth_probability,
+            flip_probability,
+        })
+    }
+
+    /// The probability of a birth called for by the rule actually happening.
+    pub fn birth_probability(&self) -> f64 {
+        self.birth_probability
+    }
+
+    /// The probability of a cell surviving when the rule says it should.
+    pub fn survive_probability(&self) -> f64 {
+        self.survive_probability
+    }
+
+    /// The probability of a dead cell coming to life on its own, regardless of its neighbors.
+    pub fn spontaneous_birth_probability(&self) -> f64 {
+        self.spontaneous_birth_probability
+    }
+
+    /// The probability of any tile flipping its state after all other rules have been applied.
+    pub fn flip_probability(&self) -> f64 {
+        self.flip_probability
+    }
+
+    /// Applies the noise to a single tile transition, given the tile state before the tick
+    /// and the state the deterministic rule chose for it.
+    pub fn apply<R>(&self, rng: &mut R, previous: TileState, next: TileState) -> TileState
+    where
+        R: Rng,
+    {
+        let alive = match (previous, next) {
+            (TileState::Dead, TileState::Alive) => rng.random_bool(self.birth_probability),
+            (TileState::Alive, TileState::Alive) => rng.random_bool(self.survive_probability),
+            (TileState::Dead, TileState::Dead) => {
+                rng.random_bool(self.spontaneous_birth_probability)
+            }
+            (TileState::Alive, TileState::Dead) => false,
+        };
+
+        let flipped = rng.random_bool(self.flip_probability);
+
+        if alive != flipped {
+            TileState::Alive
+        } else {
+            TileState::Dead
+        }
+    }
+}
+
+/// A RuleNoise probability that isn't between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidProbabilityError {
+    pub name: &'static str,
+    pub probability: f64,
+}
+
+impl fmt::Display for InvalidProbabilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) must be between 0 and 1",
+            self.name, self.probability
+        )
+    }
+}
+
+impl Error for InvalidProbabilityError {}
+
+/// RuleNoise as it's deserialized, before the probabilities have been checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(default)]
+struct UncheckedRuleNoise {
+    birth_probability: f64,
+    survive_probability: f64,
+    spontaneous_birth_probability: f64,
+    flip_probability: f64,
+}
+
+#[cfg(feature = "serde")]
+impl Default for UncheckedRuleNoise {
+    fn default() -> Self {
+        let noise = RuleNoise::default();
+
+        Self {
+            birth_probability: noise.birth_probability,
+            survive_probability: noise.survive_probability,
+            spontaneous_birth_probability: noise.spontaneous_birth_probability,
+            flip_probability: noise.flip_probability,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedRuleNoise> for RuleNoise {
+    type Error = InvalidProbabilityError;
+
+    fn try_from(unchecked: UncheckedRuleNoise) -> Result<Self, Self::Error> {
+        Self::new(
+            unchecked.birth_probability,
+            unchecked.survive_probability,
+            unchecked.spontaneous_birth_probability,
+            unchecked.flip_probability,
+        )
+    }
+}
@@ -0,0 +1,285 @@
+use std::{error::Error, fmt};
+
+use super::{pos::Position, rule::Rule};
+
+/// Assigns rules to regions of a board, allowing different parts of the same board to follow different rules.
+/// The map always has a region for every tile and every region refers to one of its rules.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedRuleMap"))]
+pub struct RuleMap {
+    width: usize,
+    height: usize,
+
+    /// The rules referenced by the regions.
+    rules: Vec<Rule>,
+
+    /// The index of the rule each tile follows, laid out the same way as the tiles of a board.
+    regions: Vec<usize>,
+}
+
+impl RuleMap {
+    /// Creates a map where the whole board follows the given rule.
+    pub fn new(width: usize, height: usize, rule: Rule) -> Self {
+        Self {
+            width,
+            height,
+            rules: vec![rule],
+            regions: vec![0; width * height],
+        }
+    }
+
+    /// Creates a map from the rule index of every tile, failing if there isn't one for each tile
+    /// or if any of them doesn't refer to one of the rules.
+    pub fn from_regions(
+        width: usize,
+        height: usize,
+        rules: Vec<Rule>,
+        regions: Vec<usize>,
+    ) -> Result<Self, RuleMapError> {
+        let region_count = width
+            .checked_mul(height)
+            .ok_or(RuleMapError::SizeOverflow { width, height })?;
+
+        if regions.len() != region_count {
+            return Err(RuleMapError::RegionCountMismatch {
+                expected: region_count,
+                actual: regions.len(),
+            });
+        }
+
+        if let Some(rule_index) = regions.iter().find(|rule_index| **rule_index >= rules.len()) {
+            return Err(RuleMapError::UnknownRule {
+                rule_index: *rule_index,
+                rule_count: rules.len(),
+            });
+        }
+
+        Ok(Self {
+            width,
+            height,
+            rules,
+            regions,
+        })
+    }
+
+    /// Creates a map where a border of the given width follows the edge rule, and everything else the center rule.
+    pub fn new_center_and_edges(
+        width: usize,
+        height: usize,
+        edge_width: usize,
+        center_rule: Rule,
+        edge_rule: Rule,
+    ) -> Self {
+        let mut rule_map = Self::new(width, height, edge_rule);
+        let center_rule_index = rule_map.add_rule(center_rule);
+
+        let center_from = Position {
+            x: edge_width,
+            y: edge_width,
+        };
+
+        let center_to = Position {
+            x: width.saturating_sub(edge_width),
+            y: height.saturating_sub(edge_width),
+        };
+
+        // SAFETY: The index was just returned by add_rule, so unwrap should always be OK.
+        rule_map
+            .fill_rect(center_from, center_to, center_rule_index)
+            .unwrap();
+
+        rule_map
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The rules referenced by the regions.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// The index of the rule each tile follows, laid out the same way as the tiles of a board.
+    pub fn regions(&self) -> &[usize] {
+        &self.regions
+    }
+
+    /// Adds a rule to the map, returning the index regions can refer to it by.
+    pub fn add_rule(&mut self, rule: Rule) -> usize {
+        self.rules.push(rule);
+        self.rules.len() - 1
+    }
+
+    /// Makes the given tile follow the rule at the given index, failing if there's no such rule.
+    /// Positions outside of the map are ignored.
+    pub fn set_region<P>(&mut self, pos: P, rule_index: usize) -> Result<(), RuleMapError>
+    where
+        P: Into<Position>,
+    {
+        self.check_rule_index(rule_index)?;
+
+        if let Some(index) = self.pos_to_index(pos) {
+            self.regions[index] = rule_index;
+        }
+
+        Ok(())
+    }
+
+    /// Makes every tile from (inclusive) the first position to (exclusive) the second one follow the rule at the given index,
+    /// failing if there's no such rule.
+    pub fn fill_rect(
+        &mut self,
+        from: Position,
+        to: Position,
+        rule_index: usize,
+    ) -> Result<(), RuleMapError> {
+        self.check_rule_index(rule_index)?;
+
+        for y in from.y..to.y.min(self.height) {
+            for x in from.x..to.x.min(self.width) {
+                self.set_region(Position { x, y }, rule_index)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the map covers a board of the given size exactly.
+    pub fn check_size(&self, width: usize, height: usize) -> Result<(), RuleMapError> {
+        if self.width != width || self.height != height {
+            return Err(RuleMapError::SizeMismatch {
+                map_width: self.width,
+                map_height: self.height,
+                board_width: width,
+                board_height: height,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The rule the given tile follows, if the tile is on the map and refers to an existing rule.
+    pub fn rule<P>(&self, pos: P) -> Option<&Rule>
+    where
+        P: Into<Position>,
+    {
+        let index = self.pos_to_index(pos)?;
+        let rule_index = self.regions.get(index)?;
+        self.rules.get(*rule_index)
+    }
+
+    fn check_rule_index(&self, rule_index: usize) -> Result<(), RuleMapError> {
+        if rule_index >= self.rules.len() {
+            return Err(RuleMapError::UnknownRule {
+                rule_index,
+                rule_count: self.rules.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn pos_to_index<P>(&self, pos: P) -> Option<usize>
+    where
+        P: Into<Position>,
+    {
+        let Position { x, y } = pos.into();
+
+        if x >= self.width {
+            return None;
+        }
+
+        if y >= self.height {
+            return None;
+        }
+
+        Some(x + (y * self.width))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleMapError {
+    SizeOverflow {
+        width: usize,
+        height: usize,
+    },
+    RegionCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    UnknownRule {
+        rule_index: usize,
+        rule_count: usize,
+    },
+    SizeMismatch {
+        map_width: usize,
+        map_height: usize,
+        board_width: usize,
+        board_height: usize,
+    },
+}
+
+impl fmt::Display for RuleMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SizeOverflow { width, height } => {
+                write!(f, "Rule map size {}x{} overflows", width, height)
+            }
+            Self::RegionCountMismatch { expected, actual } => write!(
+                f,
+                "Expected {} regions in the rule map, got {}",
+                expected, actual
+            ),
+            Self::UnknownRule {
+                rule_index,
+                rule_count,
+            } => write!(
+                f,
+                "Region refers to rule {}, but the rule map only has {} rules",
+                rule_index, rule_count
+            ),
+            Self::SizeMismatch {
+                map_width,
+                map_height,
+                board_width,
+                board_height,
+            } => write!(
+                f,
+                "Rule map is {}x{}, but the board is {}x{}",
+                map_width, map_height, board_width, board_height
+            ),
+        }
+    }
+}
+
+impl Error for RuleMapError {}
+
+/// RuleMap as it's deserialized, before the regions have been checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedRuleMap {
+    width: usize,
+    height: usize,
+    rules: Vec<Rule>,
+    regions: Vec<usize>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedRuleMap> for RuleMap {
+    type Error = RuleMapError;
+
+    fn try_from(unchecked: UncheckedRuleMap) -> Result<Self, Self::Error> {
+        Self::from_regions(
+            unchecked.width,
+            unchecked.height,
+            unchecked.rules,
+            unchecked.regions,
+        )
+    }
+}
@@ -0,0 +1,36 @@
+use super::pos::{Offset, Position};
+
+/// How the edges of a board behave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Topology {
+    /// Everything beyond the edges is outside of the board.
+    #[default]
+    Bounded,
+
+    /// The edges wrap around to the opposite side of the board.
+    Toroidal,
+}
+
+impl Topology {
+    /// Offsets a position on a board of the given size, returning None if the result is outside of the board.
+    pub fn offset_position(
+        self,
+        pos: Position,
+        offset: Offset,
+        width: usize,
+        height: usize,
+    ) -> Option<Position> {
+        if pos.x >= width || pos.y >= height {
+            return None;
+        }
+
+        match self {
+            Topology::Bounded => pos
+                .checked_add_offset(offset)
+                .filter(|offset_pos| offset_pos.x < width && offset_pos.y < height),
+            Topology::Toroidal => Some(pos.wrapping_add_offset(offset, width, height)),
+        }
+    }
+}
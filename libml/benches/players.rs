@@ -0,0 +1,112 @@
+//! Compares choosing a move with NetworkPlayer and ConvNetworkPlayer on the same board.
+//! Both networks see the 5x5 tiles around each position and do the same number of multiplications per position:
+//! the kernel network has a hidden layer of 8 nodes, the convolutional one two 3x3 layers with 8 hidden channels.
+
+#![feature(test)]
+
+extern crate test;
+
+use libgame::{Game, board::GameBoard, rule::Rule};
+use libml::{
+    game::{
+        NetworkPlayer, NetworkPlayerConfig,
+        conv::{ConvNetworkPlayer, ConvNetworkPlayerConfig},
+        kernel::ContextFeatures,
+    },
+    network::{
+        ActivationPlacement, Network, NetworkConfig,
+        conv::ConvNetwork,
+        functions::{Activator, Combinator},
+        layer::conv::Padding,
+        node::NodeInput,
+    },
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use test::Bencher;
+
+const BOARD_SIZE: usize = 16;
+const HIDDEN_SIZE: usize = 8;
+
+fn new_game() -> Game {
+    let board = GameBoard::new_random(BOARD_SIZE, BOARD_SIZE, BOARD_SIZE.pow(2) / 2, 1);
+    Game::new(board, Rule::default())
+}
+
+#[bench]
+fn kernel_player_choose_move(bencher: &mut Bencher) {
+    let rng = &mut StdRng::seed_from_u64(0);
+
+    let player_config = NetworkPlayerConfig {
+        kernel_diameter: 5,
+        use_kernel_cache: false,
+        use_tile_ages: false,
+        memory_channels: 0,
+        context_features: ContextFeatures::default(),
+        preview_ticks: 0,
+        use_neighbour_counts: false,
+        history_depth: 0,
+    };
+
+    let mut network = Network::new(
+        NetworkConfig {
+            activator: Activator::Tanh,
+            combinator: Combinator::Add,
+            activation_placement: ActivationPlacement::ActivateSums,
+        },
+        player_config.input_count(),
+        1,
+        HIDDEN_SIZE,
+        player_config.output_count(),
+    );
+
+    // Fully connected, so that the layers are computed densely.
+    let mut previous_height = player_config.input_count();
+    for comp_layer in &mut network.compute_layers {
+        for node in &mut comp_layer.nodes {
+            node.inputs = (0..previous_height)
+                .map(|source_node_index| {
+                    NodeInput::new(source_node_index, rng.random_range(-0.5..0.5))
+                })
+                .collect();
+        }
+
+        previous_height = comp_layer.nodes.len();
+    }
+
+    let mut player = NetworkPlayer::new(player_config, network);
+    let game = new_game();
+
+    bencher.iter(|| player.choose_move(&game));
+}
+
+#[bench]
+fn conv_player_choose_move(bencher: &mut Bencher) {
+    let rng = &mut StdRng::seed_from_u64(0);
+
+    let player_config = ConvNetworkPlayerConfig {
+        use_tile_ages: false,
+    };
+
+    let mut network = ConvNetwork::new(
+        Activator::Tanh,
+        3,
+        Padding::Zeros,
+        player_config.input_channels(),
+        1,
+        HIDDEN_SIZE,
+        player_config.output_channels(),
+    );
+
+    for parameter in network
+        .layers
+        .iter_mut()
+        .flat_map(|layer| layer.parameters_mut())
+    {
+        *parameter = rng.random_range(-0.5..0.5);
+    }
+
+    let player = ConvNetworkPlayer::new(player_config, network);
+    let game = new_game();
+
+    bencher.iter(|| player.choose_move(&game));
+}
@@ -0,0 +1,160 @@
+use std::borrow::Cow;
+
+use itertools::Itertools;
+use libgame::{Game, pos::Position};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::network::{
+    conv::ConvNetwork,
+    layer::conv::FeatureMap,
+    validation::{ValidationError, ValidationErrors},
+};
+
+use super::{
+    KernelOutput, NetworkPlayerMove, best_scored_position, chosen_move,
+    kernel::{age_input_value, tile_input_value},
+};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ConvNetworkPlayerConfig {
+    /// Whether to feed the network the number of generations each tile has been in its current state,
+    /// see `NetworkPlayerConfig::use_tile_ages`.
+    #[serde(default)]
+    pub use_tile_ages: bool,
+}
+
+impl ConvNetworkPlayerConfig {
+    /// The input channels the network needs to have for the boards of this config.
+    pub fn input_channels(&self) -> usize {
+        1 + usize::from(self.use_tile_ages)
+    }
+
+    /// The minimum output channels the network needs to have, the score map and the state map as in KernelOutput.
+    pub fn output_channels(&self) -> usize {
+        2
+    }
+
+    /// Checks that the network is valid and fits this config.
+    pub fn validate_network(&self, network: &ConvNetwork) -> Result<(), ValidationErrors> {
+        let mut errors = network.find_validation_errors();
+
+        if network.input_channels() != self.input_channels() {
+            errors.push(ValidationError::InputCountMismatch {
+                expected: self.input_channels(),
+                actual: network.input_channels(),
+            });
+        }
+
+        if !network.layers.is_empty() && network.output_channels() < self.output_channels() {
+            errors.push(ValidationError::TooFewOutputs {
+                expected: self.output_channels(),
+                actual: network.output_channels(),
+            });
+        }
+
+        ValidationErrors::into_result(errors)
+    }
+}
+
+/// Plays like NetworkPlayer, but maps the whole board to the scores and states of every position in one pass
+/// instead of computing a separate kernel for each of them, so that the network can see past a single kernel.
+/// The padding of the layers decides how the network sees the edges of the board, e.g. Wrap suits toroidal boards.
+///
+/// It's also faster, choosing a move in about two thirds of the time NetworkPlayer takes with a network which sees
+/// as far and does as many multiplications per position, see `benches/players.rs`.
+pub struct ConvNetworkPlayer<'a> {
+    pub config: ConvNetworkPlayerConfig,
+    network: Cow<'a, ConvNetwork>,
+}
+
+impl<'a> ConvNetworkPlayer<'a> {
+    /// Creates a player for either a borrowed or an owned network.
+    pub fn new<N>(config: ConvNetworkPlayerConfig, network: N) -> Self
+    where
+        N: Into<Cow<'a, ConvNetwork>>,
+    {
+        Self {
+            config,
+            network: network.into(),
+        }
+    }
+
+    pub fn network(&self) -> &ConvNetwork {
+        &self.network
+    }
+
+    pub fn play_step(&mut self, game: &mut Game) -> Option<NetworkPlayerMove> {
+        if self.config.use_tile_ages {
+            game.board.track_ages();
+        }
+
+        let network_move = self.choose_move(game)?;
+
+        // SAFETY: The compute method doesn't let the network give arbitrary positions,
+        //         so positions will always correspond to a tile.
+        *game.board.tile_mut(network_move.position).unwrap() = network_move.new_state;
+
+        Some(network_move)
+    }
+
+    /// Chooses the move the network wants to make without applying it, if it wants to change anything at all.
+    pub fn choose_move(&self, game: &Game) -> Option<NetworkPlayerMove> {
+        let output_map = self.compute_board(game);
+
+        // Shuffled so that ties don't always go to the same corner of the board.
+        let mut positions = (0..game.board.width)
+            .cartesian_product(0..game.board.height)
+            .map(|(x, y)| Position { x, y })
+            .collect_vec();
+        positions.shuffle(&mut rand::rng());
+
+        let scored_positions = positions.into_iter().map(|position| {
+            let output_values = output_map.position_values(position.x, position.y);
+
+            let output = KernelOutput {
+                score: output_values[0],
+                state: output_values[1],
+            };
+
+            (position, output)
+        });
+
+        let (chosen_position, output) = best_scored_position(scored_positions)?;
+        chosen_move(game, chosen_position, output)
+    }
+
+    /// Lets the network map the board to its outputs, the score and state of each position being the first two channels.
+    pub fn compute_board(&self, game: &Game) -> FeatureMap {
+        let input_map = self.input_map(game);
+
+        let output_map = self.network.compute(&input_map);
+        assert!(
+            output_map.channels() >= 2,
+            "Not enough output channels in conv network"
+        );
+
+        output_map
+    }
+
+    fn input_map(&self, game: &Game) -> FeatureMap {
+        let mut input_map = FeatureMap::zeros(
+            game.board.width,
+            game.board.height,
+            self.config.input_channels(),
+        );
+
+        for (x, y) in (0..game.board.width).cartesian_product(0..game.board.height) {
+            let position = Position { x, y };
+            let input_values = input_map.position_values_mut(x, y);
+
+            input_values[0] = tile_input_value(game.board.tile(position).copied());
+
+            if self.config.use_tile_ages {
+                input_values[1] = age_input_value(game.board.tile_age(position).unwrap_or(0));
+            }
+        }
+
+        input_map
+    }
+}
@@ -0,0 +1,296 @@
+use std::sync::Arc;
+
+use libgame::{
+    Game,
+    board::{GameBoard, TileState},
+    rule::Rule,
+    rulemap::{RuleMap, RuleMapError},
+    topology::Topology,
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+use super::NetworkPlayerMove;
+
+/// The action taken in a single step, None meaning that the turn is skipped.
+pub type Action = Option<NetworkPlayerMove>;
+
+pub trait RewardFunction = Fn(&Transition) -> f32 + Send + Sync;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EnvironmentConfig {
+    pub width: usize,
+    pub height: usize,
+
+    /// The amount of alive cells to spawn at the start of a game.
+    pub alive_cells: usize,
+
+    /// The size of a single "block" of cells to spawn, a value of one will result in completely random spawning,
+    /// while a value of two will result in 2x2 clusters of alive cells. The only reason this is implemented is
+    /// because the networks forgot how to kill 2x2 blocks after a while of training (lol).
+    /// NOTE: alive_cells still refers to "cells" instead of "blocks", but will round down to a block cell count.
+    pub block_size: usize,
+
+    /// The maximum number of rounds a single game will be played for.
+    /// If all cells are dead earlier, the game will stop early.
+    pub max_rounds: usize,
+
+    /// Disable any "natural" progression of the game completely,
+    /// leaving only the agent to make changes to the board, this may be useful in the beginning of training.
+    pub disable_nature: bool,
+
+    /// Whether to reward for cells killed or cells brought to life.
+    pub evil: bool,
+
+    /// The rule the games are played by.
+    #[serde(default)]
+    pub rule: Rule,
+
+    /// Optional rules for specific regions of the board, overriding the main rule.
+    /// Must be the same size as the board.
+    #[serde(default)]
+    pub rule_map: Option<RuleMap>,
+
+    /// How the edges of the board behave.
+    #[serde(default)]
+    pub topology: Topology,
+
+    /// Whether to track the ages of the tiles, for agents that look at them.
+    #[serde(default)]
+    pub track_ages: bool,
+}
+
+/// What an agent gets to see of the game, borrowed from the environment so that observing doesn't copy the board.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Observation<'a> {
+    pub board: &'a GameBoard,
+
+    /// The number of rounds played so far.
+    pub round: usize,
+    pub rounds_left: usize,
+}
+
+/// A single step of the game, which rewards are calculated from.
+#[derive(Debug, Clone, Copy)]
+pub struct Transition {
+    pub alive_cells_before: usize,
+    pub alive_cells_after: usize,
+
+    /// Whether the action actually changed a tile.
+    pub action_applied: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct StepResult<'a> {
+    pub observation: Observation<'a>,
+    pub reward: f32,
+    pub done: bool,
+    pub info: StepInfo,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StepInfo {
+    pub alive_cells: usize,
+
+    /// Whether the turn was skipped, either on purpose or because the action didn't change anything.
+    pub skipped: bool,
+
+    /// Whether the action was rejected for pointing outside of the board.
+    pub invalid_action: bool,
+}
+
+/// A single game as played by an agent: the agent may change one tile per round, after which nature takes its course.
+#[derive(Clone)]
+pub struct Environment {
+    config: EnvironmentConfig,
+    reward_function: Arc<dyn RewardFunction>,
+    game: Game,
+    round: usize,
+
+    total_reward: f32,
+    skipped_turns: usize,
+    baseline_reward: f32,
+}
+
+impl Environment {
+    /// Creates an environment with an empty board, call reset to start a game.
+    /// Fails if the rule map of the config isn't the same size as the board.
+    pub fn new(config: EnvironmentConfig) -> Result<Self, RuleMapError> {
+        let evil = config.evil;
+        let reward_function = move |transition: &Transition| {
+            let killed_cells =
+                transition.alive_cells_before as f32 - transition.alive_cells_after as f32;
+
+            if evil { killed_cells } else { -killed_cells }
+        };
+
+        if let Some(rule_map) = &config.rule_map {
+            rule_map.check_size(config.width, config.height)?;
+        }
+
+        let game = Self::new_game(&config, GameBoard::new(config.width, config.height), 0);
+
+        Ok(Self {
+            config,
+            reward_function: Arc::new(reward_function),
+            game,
+            round: 0,
+            total_reward: 0.0,
+            skipped_turns: 0,
+            baseline_reward: 0.0,
+        })
+    }
+
+    pub fn with_reward_function<F>(mut self, reward_function: F) -> Self
+    where
+        F: RewardFunction + 'static,
+    {
+        self.reward_function = Arc::new(reward_function);
+        self
+    }
+
+    /// Starts a new game. Games started with the same seed are identical.
+    pub fn reset(&mut self, seed: u64) -> Observation<'_> {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let board = GameBoard::new_random_with_rng(
+            self.config.width,
+            self.config.height,
+            self.config.alive_cells,
+            self.config.block_size,
+            &mut rng,
+        );
+
+        self.game = Self::new_game(&self.config, board, rng.random());
+        self.round = 0;
+        self.total_reward = 0.0;
+        self.skipped_turns = 0;
+        self.baseline_reward = self.play_out_baseline();
+
+        self.observe()
+    }
+
+    pub fn step(&mut self, action: Action) -> StepResult<'_> {
+        let alive_cells_before = self.game.count_cells(TileState::Alive);
+
+        let target_tile = action.map(|action| (action, self.game.board.tile_mut(action.position)));
+        let invalid_action = matches!(target_tile, Some((_, None)));
+
+        let action_applied = match target_tile {
+            Some((action, Some(tile))) if *tile != action.new_state => {
+                *tile = action.new_state;
+                true
+            }
+            _ => false,
+        };
+
+        if !self.config.disable_nature {
+            self.game.tick();
+        }
+
+        self.round += 1;
+
+        let alive_cells = self.game.count_cells(TileState::Alive);
+
+        let reward = (self.reward_function)(&Transition {
+            alive_cells_before,
+            alive_cells_after: alive_cells,
+            action_applied,
+        });
+
+        self.total_reward += reward;
+        if !action_applied {
+            self.skipped_turns += 1;
+        }
+
+        StepResult {
+            observation: self.observe(),
+            reward,
+            done: self.is_done(),
+            info: StepInfo {
+                alive_cells,
+                skipped: !action_applied,
+                invalid_action,
+            },
+        }
+    }
+
+    pub fn observe(&self) -> Observation<'_> {
+        Observation {
+            board: &self.game.board,
+            round: self.round,
+            rounds_left: self.config.max_rounds.saturating_sub(self.round),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.round >= self.config.max_rounds || self.game.count_cells(TileState::Alive) == 0
+    }
+
+    /// The total reward nature alone would have gotten by playing out the game since the last reset.
+    pub fn baseline_reward(&self) -> f32 {
+        self.baseline_reward
+    }
+
+    /// The total reward of all steps since the last reset.
+    pub fn total_reward(&self) -> f32 {
+        self.total_reward
+    }
+
+    /// The overall score of the game so far, as used for training.
+    /// Only the reward beyond what nature would have achieved by itself counts,
+    /// and finishing early as well as skipping turns are punished.
+    pub fn score(&self) -> isize {
+        // NOTE: I'd imagine it's useful to have the actual performance based score separated from all the
+        //       "artificial" punishments (taken steps etc), as otherwise the comparison to the reference
+        //       score isn't all that fair (if I'm thinking this correctly).
+        let reward = self.total_reward as isize - self.baseline_reward as isize;
+
+        // NOTE: It's quite essential that the taken rounds punishment is divided, since otherwise
+        //       we would cancel out all the reward out of directly killed cells, which doesn't work out.
+        let taken_rounds_punishment =
+            self.config.max_rounds.saturating_sub(self.round) as isize / 2;
+
+        // Also punish for many skipped turns, this may not be totally "correct" in every circumstance,
+        // but will generally be a sign of bad behavior at the current state of the networks.
+        let skipped_turns_punishment = self.skipped_turns as isize / 5;
+
+        reward - (taken_rounds_punishment + skipped_turns_punishment)
+    }
+
+    pub fn config(&self) -> &EnvironmentConfig {
+        &self.config
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    fn play_out_baseline(&self) -> f32 {
+        let mut baseline_environment = self.clone();
+
+        while !baseline_environment.is_done() {
+            baseline_environment.step(None);
+        }
+
+        baseline_environment.total_reward
+    }
+
+    fn new_game(config: &EnvironmentConfig, mut board: GameBoard, seed: u64) -> Game {
+        if config.track_ages {
+            board.track_ages();
+        }
+
+        let mut game = Game::with_seed(board, config.rule.clone(), seed);
+        game.topology = config.topology;
+
+        // SAFETY: The size of the rule map was checked against the board size when the environment was created,
+        //         and the config can't be changed afterwards, so unwrap should always be OK.
+        game.set_rule_map(config.rule_map.clone()).unwrap();
+        game
+    }
+}
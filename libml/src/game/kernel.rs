@@ -1,27 +1,213 @@
+use std::hash::{Hash, Hasher};
+
 use libgame::board::TileState;
+use serde::{Deserialize, Serialize};
+
+use crate::network::{Value, harness::InputProvider};
 
-use crate::network::harness::InputProvider;
+use super::NetworkPlayerConfig;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Kernel {
     pub tiles: Vec<Option<TileState>>,
+
+    /// The ages of the tiles, only present if enabled in the player config.
+    /// Tiles outside of the board are considered to be zero generations old.
+    pub ages: Option<Vec<usize>>,
+
+    /// The memory channel values of the tiles one tile after another, see `NetworkPlayerConfig::memory_channels`.
+    /// Tiles outside of the board remember nothing, as do kernels without memory, e.g. ones scored outside of a game.
+    #[serde(default)]
+    pub memory: Option<Vec<Value>>,
+
+    /// Features of the whole game around the kernel, see `NetworkPlayerConfig::context_features`.
+    #[serde(default)]
+    pub context: KernelContext,
+
+    /// What the tiles will be after the number of ticks set in the player config, see `NetworkPlayerConfig::preview_ticks`.
+    #[serde(default)]
+    pub preview: Option<Vec<Option<TileState>>>,
+
+    /// The number of alive neighbours of each tile, only present if enabled in the player config.
+    /// Tiles outside of the board are considered to have none.
+    #[serde(default)]
+    pub neighbour_counts: Option<Vec<u8>>,
+
+    /// The tiles on the boards of the previous moves one board after another, see `NetworkPlayerConfig::history_depth`.
+    #[serde(default)]
+    pub history: Option<Vec<Option<TileState>>>,
+}
+
+// Memory and context values are compared by their bits, so that kernels can still be used as keys of the kernel cache.
+impl PartialEq for Kernel {
+    fn eq(&self, other: &Self) -> bool {
+        self.tiles == other.tiles
+            && self.ages == other.ages
+            && self.memory.is_some() == other.memory.is_some()
+            && self.memory_bits().eq(other.memory_bits())
+            && self.context.value_bits().eq(other.context.value_bits())
+            && self.preview == other.preview
+            && self.neighbour_counts == other.neighbour_counts
+            && self.history == other.history
+    }
+}
+
+impl Eq for Kernel {}
+
+impl Hash for Kernel {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.tiles.hash(state);
+        self.ages.hash(state);
+        self.memory.is_some().hash(state);
+        self.memory_bits().for_each(|bits| bits.hash(state));
+        self.context.value_bits().for_each(|bits| bits.hash(state));
+        self.preview.hash(state);
+        self.neighbour_counts.hash(state);
+        self.history.hash(state);
+    }
+}
+
+/// Which features of the whole game are fed to kernel networks along with the tiles, see KernelContext.
+/// These let networks tell an almost empty board from a full one, or the start of a game from its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextFeatures {
+    pub population: bool,
+    pub rounds_left: bool,
+    pub coordinates: bool,
+    pub edge_distance: bool,
+
+    /// How many coarser scales of regions around the kernel to feed the population of.
+    pub region_population_scales: usize,
+}
+
+/// A single input of the context features, in the order they're fed to the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextInput {
+    Population,
+    RoundsLeft,
+    CoordinateX,
+    CoordinateY,
+    EdgeDistance,
+    RegionPopulation(usize),
+}
+
+impl ContextFeatures {
+    pub fn inputs(&self) -> impl Iterator<Item = ContextInput> + use<> {
+        [
+            (self.population, ContextInput::Population),
+            (self.rounds_left, ContextInput::RoundsLeft),
+            (self.coordinates, ContextInput::CoordinateX),
+            (self.coordinates, ContextInput::CoordinateY),
+            (self.edge_distance, ContextInput::EdgeDistance),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, input)| input)
+        .chain((0..self.region_population_scales).map(ContextInput::RegionPopulation))
+    }
+
+    pub fn input_count(&self) -> usize {
+        self.inputs().count()
+    }
+}
+
+/// The values of the context features for a kernel, all of them normalized to [0, 1].
+/// Only the features enabled in the player config are filled in, and missing ones are fed to the network as zeros.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KernelContext {
+    /// The share of alive tiles on the whole board.
+    pub population: Option<Value>,
+
+    /// The share of the rounds of the game which are left, see `NetworkPlayer::set_rounds_left`.
+    pub rounds_left: Option<Value>,
+
+    /// The position of the kernel center on the board.
+    pub coordinates: Option<[Value; 2]>,
+
+    /// How far the kernel center is from the nearest edge of the board, one being the middle of the board.
+    /// Toroidal boards don't have edges, so every position is as far from them as possible.
+    pub edge_distance: Option<Value>,
+
+    /// The share of alive tiles in the region around the kernel center at each scale, ignoring tiles outside of the board.
+    /// The region of the first scale reaches a kernel diameter away from the center, and each further one twice as far.
+    pub region_populations: Vec<Value>,
+}
+
+impl KernelContext {
+    pub fn input_value(&self, input: ContextInput) -> Value {
+        let value = match input {
+            ContextInput::Population => self.population,
+            ContextInput::RoundsLeft => self.rounds_left,
+            ContextInput::CoordinateX => self.coordinates.map(|[x, _]| x),
+            ContextInput::CoordinateY => self.coordinates.map(|[_, y]| y),
+            ContextInput::EdgeDistance => self.edge_distance,
+            ContextInput::RegionPopulation(scale) => self.region_populations.get(scale).copied(),
+        };
+
+        value.unwrap_or(0.0)
+    }
+
+    fn value_bits(&self) -> impl Iterator<Item = Option<u32>> {
+        let [x, y] = self
+            .coordinates
+            .map_or([None, None], |coordinates| coordinates.map(Some));
+
+        [self.population, self.rounds_left, x, y, self.edge_distance]
+            .into_iter()
+            .chain(self.region_populations.iter().copied().map(Some))
+            .map(|value| value.map(Value::to_bits))
+    }
 }
 
 impl Kernel {
     pub fn input_providers(
-        kernel_diameter: usize,
+        config: &NetworkPlayerConfig,
     ) -> impl Iterator<Item = Box<dyn InputProvider<Self>>> {
-        let tile_count = kernel_diameter.pow(2);
+        let tile_count = config.kernel_diameter.pow(2);
+        let age_count = if config.use_tile_ages { tile_count } else { 0 };
+        let memory_count = tile_count * config.memory_channels;
+        let preview_count = if config.preview_ticks > 0 {
+            tile_count
+        } else {
+            0
+        };
+        let neighbour_count_count = if config.use_neighbour_counts {
+            tile_count
+        } else {
+            0
+        };
+        let history_count = tile_count * config.history_depth;
 
-        (0..tile_count).map(|tile_index| {
-            // Rust can't yet infer the lifetime of this closure so we need to explicitly tell that it's unbounded.
-            let input_provider: impl for<'a> InputProvider<Self> =
-                move |kernel| Self::input_provider(tile_index, kernel);
+        fn boxed(
+            input_provider: impl Fn(&Kernel) -> Value + Send + Sync + 'static,
+        ) -> Box<dyn InputProvider<Kernel>> {
+            Box::new(input_provider)
+        }
 
-            // Also needs a bit help here.
-            let boxed_input_provider: Box<dyn InputProvider<_>> = Box::new(input_provider);
-            boxed_input_provider
-        })
+        fn indexed(
+            count: usize,
+            input_provider: fn(usize, &Kernel) -> Value,
+        ) -> impl Iterator<Item = Box<dyn InputProvider<Kernel>>> {
+            (0..count).map(move |index| boxed(move |kernel| input_provider(index, kernel)))
+        }
+
+        let context_providers = config
+            .context_features
+            .inputs()
+            .map(|context_input| boxed(move |kernel| kernel.context.input_value(context_input)));
+
+        indexed(tile_count, Self::input_provider)
+            .chain(indexed(age_count, Self::age_input_provider))
+            .chain(indexed(memory_count, Self::memory_input_provider))
+            .chain(context_providers)
+            .chain(indexed(preview_count, Self::preview_input_provider))
+            .chain(indexed(
+                neighbour_count_count,
+                Self::neighbour_count_input_provider,
+            ))
+            .chain(indexed(history_count, Self::history_input_provider))
     }
 
     fn input_provider(tile_index: usize, kernel: &Self) -> f32 {
@@ -30,10 +216,75 @@ impl Kernel {
             .get(tile_index)
             .expect("Not enough input tiles");
 
-        match tile {
-            Some(TileState::Alive) => 1.0,
-            None => -0.0,
-            Some(TileState::Dead) => -1.0,
-        }
+        tile_input_value(*tile)
+    }
+
+    fn age_input_provider(tile_index: usize, kernel: &Self) -> f32 {
+        let age = kernel
+            .ages
+            .as_ref()
+            .and_then(|ages| ages.get(tile_index))
+            .expect("Not enough input tile ages");
+
+        age_input_value(*age)
+    }
+
+    fn memory_input_provider(memory_index: usize, kernel: &Self) -> f32 {
+        kernel.memory.as_ref().map_or(0.0, |memory| {
+            *memory
+                .get(memory_index)
+                .expect("Not enough input memory values")
+        })
     }
+
+    // Kernels without a preview or neighbour counts, e.g. ones scored outside of a game, see them as zeros.
+    fn preview_input_provider(tile_index: usize, kernel: &Self) -> f32 {
+        kernel.preview.as_ref().map_or(0.0, |preview| {
+            tile_input_value(*preview.get(tile_index).expect("Not enough preview tiles"))
+        })
+    }
+
+    fn neighbour_count_input_provider(tile_index: usize, kernel: &Self) -> f32 {
+        kernel
+            .neighbour_counts
+            .as_ref()
+            .map_or(0.0, |neighbour_counts| {
+                let neighbour_count = neighbour_counts
+                    .get(tile_index)
+                    .expect("Not enough neighbour counts");
+
+                // There are at most eight neighbours.
+                *neighbour_count as f32 / 8.0
+            })
+    }
+
+    // Kernels without history, e.g. ones scored outside of a game, see zeros instead.
+    fn history_input_provider(history_index: usize, kernel: &Self) -> f32 {
+        kernel.history.as_ref().map_or(0.0, |history| {
+            tile_input_value(
+                *history
+                    .get(history_index)
+                    .expect("Not enough history tiles"),
+            )
+        })
+    }
+
+    fn memory_bits(&self) -> impl Iterator<Item = u32> {
+        self.memory.iter().flatten().map(|value| value.to_bits())
+    }
+}
+
+/// How a tile is fed to networks, None being outside of the board.
+pub(crate) fn tile_input_value(tile: Option<TileState>) -> Value {
+    match tile {
+        Some(TileState::Alive) => 1.0,
+        None => -0.0,
+        Some(TileState::Dead) => -1.0,
+    }
+}
+
+/// How the age of a tile is fed to networks.
+pub(crate) fn age_input_value(age: usize) -> Value {
+    // Squash the unbounded age into [0, 1) so that old still lifes don't blow up the network.
+    1.0 - 1.0 / (age as Value + 1.0)
 }
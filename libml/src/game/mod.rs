@@ -1,15 +1,36 @@
-use std::{cmp::Ordering, collections::HashMap};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{HashMap, VecDeque},
+    iter,
+    ops::Range,
+    slice,
+};
 
 use itertools::Itertools;
-use kernel::Kernel;
-use libgame::{Game, board::TileState, pos::Position};
+use kernel::{ContextFeatures, Kernel, KernelContext};
+use libgame::{
+    Game,
+    board::{GameBoard, TileState},
+    pos::Position,
+    topology::Topology,
+};
 use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 
-use crate::network::{Network, harness::NetworkHarness};
+use crate::network::{
+    Network, Value,
+    compiled::{CompiledNetwork, InferenceBuffers},
+    harness::NetworkHarness,
+    layer::Layer,
+    validation::{ValidationError, ValidationErrors},
+};
 
+pub mod conv;
+pub mod environment;
 pub mod kernel;
 pub mod networksave;
+pub mod supervised;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct NetworkPlayerConfig {
@@ -18,62 +39,230 @@ pub struct NetworkPlayerConfig {
     /// Whether to reuse network responses for identical kernels. This renders any kind of randomization/heat quite useless
     /// but can make training significantly faster for bigger board or kernel sizes, trading memory usage for performance.
     pub use_kernel_cache: bool,
+
+    /// Whether to feed the network the number of generations each tile in the kernel has been in its current state.
+    /// Old cells tend to belong to still lifes, which can't be told apart from fresh cells by a single snapshot.
+    #[serde(default)]
+    pub use_tile_ages: bool,
+
+    /// How many values the network remembers for each position of the board between moves of a game.
+    /// The network outputs them after the score and state, and they're fed back in for every tile of the kernel,
+    /// so that positions can keep track of what happened around them and pass it on to their neighbours.
+    #[serde(default)]
+    pub memory_channels: usize,
+
+    /// Which features of the whole game to feed the network after the values of the tiles.
+    #[serde(default)]
+    pub context_features: ContextFeatures,
+
+    /// How many generations ahead to show the network what the tiles of the kernel will be if nobody changes anything,
+    /// zero for not showing it. Killing efficiently requires anticipating births, which is cheap to compute for the network.
+    #[serde(default)]
+    pub preview_ticks: usize,
+
+    /// Whether to feed the network the number of alive neighbours each tile in the kernel has.
+    #[serde(default)]
+    pub use_neighbour_counts: bool,
+
+    /// How many of the boards from the previous moves of a game the kernel also covers, the most recent first.
+    /// These show the network how things move, e.g. which way gliders go or what phase oscillators are in.
+    #[serde(default)]
+    pub history_depth: usize,
+}
+
+impl NetworkPlayerConfig {
+    /// The input layer height the network needs to have for the kernels of this config.
+    pub fn input_count(&self) -> usize {
+        let tile_count = self.kernel_diameter.pow(2);
+        let values_per_tile = 1
+            + usize::from(self.use_tile_ages)
+            + self.memory_channels
+            + usize::from(self.preview_ticks > 0)
+            + usize::from(self.use_neighbour_counts)
+            + self.history_depth;
+
+        tile_count * values_per_tile + self.context_features.input_count()
+    }
+
+    /// The minimum output layer height the network needs to have, see KernelOutput and `memory_channels`.
+    pub fn output_count(&self) -> usize {
+        2 + self.memory_channels
+    }
+
+    /// Checks that the network is valid and fits this config.
+    pub fn validate_network(&self, network: &Network) -> Result<(), ValidationErrors> {
+        let mut errors = network.find_validation_errors();
+
+        let input_count = network.input_layer.output_node_indices().len();
+        if input_count != self.input_count() {
+            errors.push(ValidationError::InputCountMismatch {
+                expected: self.input_count(),
+                actual: input_count,
+            });
+        }
+
+        // An input only network would output its inputs, but those are already checked above.
+        if let Some(output_layer) = network.compute_layers.last()
+            && output_layer.nodes.len() < self.output_count()
+        {
+            errors.push(ValidationError::TooFewOutputs {
+                expected: self.output_count(),
+                actual: output_layer.nodes.len(),
+            });
+        }
+
+        ValidationErrors::into_result(errors)
+    }
 }
 
 pub struct NetworkPlayer<'a> {
     pub config: NetworkPlayerConfig,
+
+    /// Provides the inputs for the network, which is compiled when the player is created.
+    /// Changes made to the network through the harness won't affect the player.
     pub network_harness: NetworkHarness<'a, Kernel>,
+    compiled_network: CompiledNetwork,
+    inference_batch: InferenceBatch,
+
     kernel_cache: Option<HashMap<Kernel, KernelOutput>>,
+    memory: PlayerMemory,
+
+    /// The share of the rounds of the game which are left, if the player has been told.
+    rounds_left: Option<Value>,
+
+    /// The boards of the previous moves, the most recent first, see `NetworkPlayerConfig::history_depth`.
+    history: VecDeque<GameBoard>,
+}
+
+/// Memory reused between moves, so that scoring a board doesn't allocate for every position.
+#[derive(Debug, Default)]
+struct InferenceBatch {
+    inputs: Vec<Value>,
+    outputs: Vec<Value>,
+    recurrent_values: Vec<Value>,
+    buffers: InferenceBuffers,
+}
+
+/// Features of the whole board for a single move, computed once instead of for every kernel.
+/// Each of them is only present if the player config needs it.
+struct BoardFeatures {
+    /// The share of alive tiles on the board.
+    population: Option<Value>,
+
+    /// The board after the preview ticks, see `NetworkPlayerConfig::preview_ticks`.
+    preview: Option<GameBoard>,
+
+    /// The number of alive neighbours of each tile, in the same order as the tiles of the board.
+    neighbour_counts: Option<Vec<u8>>,
 }
 
+/// What the player remembers about each position of the board between moves, see `NetworkPlayer::reset_memory`.
+/// Positions are stored column by column.
+#[derive(Debug, Default)]
+struct PlayerMemory {
+    /// The width and height of the board the memory is for, so that it's forgotten when the board changes size.
+    board_size: (usize, usize),
+
+    /// The values of the memory channels of each position, see `NetworkPlayerConfig::memory_channels`.
+    channel_values: Vec<Value>,
+
+    /// The values of the recurrent nodes of the network from when each position was last scored.
+    recurrent_values: Vec<Value>,
+}
+
+impl PlayerMemory {
+    /// Where the values of a position are, given how many values there are for each position.
+    fn position_range(&self, position: Position, values_per_position: usize) -> Range<usize> {
+        let position_index = position.x * self.board_size.1 + position.y;
+        let first_value_index = position_index * values_per_position;
+
+        first_value_index..first_value_index + values_per_position
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NetworkPlayerMove {
     pub position: Position,
     pub new_state: TileState,
 }
 
 impl<'a> NetworkPlayer<'a> {
-    pub fn new(config: NetworkPlayerConfig, network: &'a mut Network) -> Self {
+    /// Creates a player for either a borrowed or an owned network.
+    pub fn new<N>(config: NetworkPlayerConfig, network: N) -> Self
+    where
+        N: Into<Cow<'a, Network>>,
+    {
+        let network = network.into();
+        let compiled_network = network.compile();
+
         let network_harness = NetworkHarness::new(network)
-            .with_inputs(Kernel::input_providers(config.kernel_diameter));
+            .with_inputs(Kernel::input_providers(&config));
+
+        // Kernels don't cover the recurrent values, so the same kernel can score differently from move to move.
+        let use_kernel_cache =
+            config.use_kernel_cache && compiled_network.recurrent_node_count() == 0;
 
         Self {
             config,
             network_harness,
-            kernel_cache: config.use_kernel_cache.then(|| HashMap::new()),
+            compiled_network,
+            inference_batch: InferenceBatch::default(),
+            kernel_cache: use_kernel_cache.then(|| HashMap::new()),
+            memory: PlayerMemory::default(),
+            rounds_left: None,
+            history: VecDeque::new(),
         }
     }
 
-    pub fn play_step(&mut self, game: &mut Game) -> Option<NetworkPlayerMove> {
-        if let Some((chosen_position, output)) = self.compute(&game) {
-            // SAFETY: The compute method doesn't let the network give arbitrary positions,
-            //         so positions will always correspond to a tile.
-            let chosen_tile = game.board.tile_mut(chosen_position).unwrap();
-
-            let wanted_state = match output.state {
-                ..-0.5 => Some(TileState::Dead),
-                0.5.. => Some(TileState::Alive),
-                _ => None,
-            };
+    /// Tells the player how many of the rounds of the game are left, for the rounds left context feature.
+    /// Players which haven't been told see it as zero.
+    pub fn set_rounds_left(&mut self, rounds_left: usize, max_rounds: usize) {
+        self.rounds_left = Some(rounds_left as Value / max_rounds.max(1) as Value);
+    }
 
-            let current_state = *chosen_tile;
-            if let Some(wanted_state) = wanted_state
-                && wanted_state != current_state
-            {
-                *chosen_tile = wanted_state;
+    /// Forgets everything remembered from earlier moves, which should be done before starting a new game.
+    pub fn reset_memory(&mut self) {
+        self.memory = PlayerMemory::default();
+        self.history.clear();
+    }
 
-                Some(NetworkPlayerMove {
-                    new_state: wanted_state,
-                    position: chosen_position,
-                })
-            } else {
-                None
-            }
-        } else {
-            None
+    /// Whether the player remembers anything between moves, through either memory channels or recurrent nodes.
+    pub fn has_memory(&self) -> bool {
+        self.config.memory_channels > 0 || self.compiled_network.recurrent_node_count() > 0
+    }
+
+    pub fn play_step(&mut self, game: &mut Game) -> Option<NetworkPlayerMove> {
+        if self.config.use_tile_ages {
+            game.board.track_ages();
         }
+
+        let network_move = self.choose_move(game)?;
+
+        // SAFETY: The compute method doesn't let the network give arbitrary positions,
+        //         so positions will always correspond to a tile.
+        *game.board.tile_mut(network_move.position).unwrap() = network_move.new_state;
+
+        Some(network_move)
+    }
+
+    /// Chooses the move the network wants to make without applying it, if it wants to change anything at all.
+    pub fn choose_move(&mut self, game: &Game) -> Option<NetworkPlayerMove> {
+        let (chosen_position, output) = self.compute(game)?;
+        chosen_move(game, chosen_position, output)
     }
 
     fn compute(&mut self, game: &Game) -> Option<(Position, KernelOutput)> {
+        if self.has_memory() {
+            self.fit_memory(game);
+        }
+
+        // Boards of a different size don't line up with the current one, so the history starts over.
+        if self.history.front().is_some_and(|board| {
+            (board.width, board.height) != (game.board.width, game.board.height)
+        }) {
+            self.history.clear();
+        }
+
         // Cartesian_product is smartie speech for all the unique combinations of items.
         let positions = (0..game.board.width)
             .cartesian_product(0..game.board.height)
@@ -84,88 +273,430 @@ impl<'a> NetworkPlayer<'a> {
         let positions = {
             let mut positions_vec = positions.collect_vec();
             positions_vec.shuffle(&mut rand::rng());
-            positions_vec.into_iter()
+            positions_vec
+        };
+
+        let board_features = self.get_board_features(game);
+
+        let kernels = positions
+            .iter()
+            .map(|pos| self.get_kernel(game, *pos, &board_features))
+            .collect_vec();
+
+        if self.config.history_depth > 0 {
+            let board = GameBoard::with_tiles(
+                game.board.width,
+                game.board.height,
+                game.board.tiles.clone(),
+            );
+
+            self.history.push_front(board);
+            self.history.truncate(self.config.history_depth);
+        }
+
+        let outputs = if self.has_memory() {
+            self.compute_kernels_with_memory(&positions, &kernels)
+        } else {
+            self.compute_kernels(&kernels)
         };
 
-        let scored_positions = positions
-            .into_iter()
-            .map(|pos| (pos, self.compute_pos(game, pos)));
-
-        // Pick the top by highest score.
-        scored_positions.into_iter().max_by(
-            |(_, KernelOutput { score: a_score, .. }), (_, KernelOutput { score: b_score, .. })| {
-                // Due to NaNs or something Rust doesn't provide Ord implementations for floats so gotta do with this.
-                if a_score > b_score {
-                    Ordering::Greater
-                } else if a_score == b_score {
-                    Ordering::Equal
-                } else {
-                    Ordering::Less
+        let scored_positions = positions.into_iter().zip(outputs);
+        best_scored_position(scored_positions)
+    }
+
+    /// Lets the network score a single kernel, as done for every position of the board when choosing a move.
+    /// Recurrent nodes start from zero, as if the kernel was scored on the first move of a game.
+    pub fn compute_kernel(&mut self, kernel: Kernel) -> KernelOutput {
+        self.compute_kernels(slice::from_ref(&kernel))[0]
+    }
+
+    /// Scores all of the kernels with a single batched computation, skipping the ones with cached outputs.
+    fn compute_kernels(&mut self, kernels: &[Kernel]) -> Vec<KernelOutput> {
+        let batch = &mut self.inference_batch;
+        batch.inputs.clear();
+
+        let mut outputs = kernels
+            .iter()
+            .map(|kernel| {
+                let cached_output = self
+                    .kernel_cache
+                    .as_ref()
+                    .and_then(|kernel_cache| kernel_cache.get(kernel).copied());
+
+                if cached_output.is_none() {
+                    self.network_harness
+                        .extend_batch_inputs(kernel, &mut batch.inputs);
                 }
-            },
-        )
+
+                cached_output
+            })
+            .collect_vec();
+
+        self.compiled_network
+            .compute_batch(&batch.inputs, &mut batch.outputs, &mut batch.buffers);
+
+        let output_count = self.compiled_network.output_count();
+        assert!(output_count >= 2, "Not enough outputs in kernel network");
+
+        let mut computed_outputs = batch
+            .outputs
+            .chunks_exact(output_count)
+            .map(KernelOutput::from_network_outputs);
+
+        kernels
+            .iter()
+            .zip(&mut outputs)
+            .map(|(kernel, output)| {
+                *output.get_or_insert_with(|| {
+                    // SAFETY: Every kernel without a cached output was added to the batch.
+                    let computed_output = computed_outputs.next().unwrap();
+
+                    if let Some(kernel_cache) = &mut self.kernel_cache {
+                        kernel_cache.insert(kernel.clone(), computed_output);
+                    }
+
+                    computed_output
+                })
+            })
+            .collect()
     }
 
-    fn compute_pos(&mut self, game: &Game, pos: Position) -> KernelOutput {
-        let kernel = self.get_kernel(game, pos);
+    /// Scores the kernels of the positions with a single batched computation like `compute_kernels`,
+    /// continuing from what each position remembers and updating it with the new memory.
+    fn compute_kernels_with_memory(
+        &mut self,
+        positions: &[Position],
+        kernels: &[Kernel],
+    ) -> Vec<KernelOutput> {
+        let batch = &mut self.inference_batch;
+        let memory = &mut self.memory;
+        let recurrent_node_count = self.compiled_network.recurrent_node_count();
+        let memory_channels = self.config.memory_channels;
 
-        if let Some(kernel_cache) = &self.kernel_cache {
-            if let Some(cached_output) = kernel_cache.get(&kernel) {
-                return *cached_output;
-            }
+        batch.inputs.clear();
+        batch.recurrent_values.clear();
+
+        for (position, kernel) in positions.iter().zip(kernels) {
+            self.network_harness
+                .extend_batch_inputs(kernel, &mut batch.inputs);
+            batch.recurrent_values.extend_from_slice(
+                &memory.recurrent_values[memory.position_range(*position, recurrent_node_count)],
+            );
         }
 
-        let mut network_outputs = self.network_harness.compute(&kernel);
+        self.compiled_network.compute_batch_recurrent(
+            &batch.inputs,
+            &mut batch.recurrent_values,
+            &mut batch.outputs,
+            &mut batch.buffers,
+        );
 
-        let mut next = || {
-            network_outputs
-                .next()
-                .expect("Not enough outputs in kernel network")
-        };
+        let output_count = self.compiled_network.output_count();
+        assert!(
+            output_count >= 2 + memory_channels,
+            "Not enough outputs in kernel network"
+        );
 
-        let output = KernelOutput {
-            score: next(),
-            state: next(),
-        };
-        if let Some(kernel_cache) = &mut self.kernel_cache {
-            drop(next);
-            drop(network_outputs);
-            kernel_cache.insert(kernel, output);
-        }
+        positions
+            .iter()
+            .zip(batch.outputs.chunks_exact(output_count))
+            .enumerate()
+            .map(|(sample_index, (position, network_outputs))| {
+                let recurrent_range = memory.position_range(*position, recurrent_node_count);
+                let channel_range = memory.position_range(*position, memory_channels);
+                let first_sample_value_index = sample_index * recurrent_node_count;
+
+                memory.recurrent_values[recurrent_range].copy_from_slice(
+                    &batch.recurrent_values
+                        [first_sample_value_index..first_sample_value_index + recurrent_node_count],
+                );
+                memory.channel_values[channel_range]
+                    .copy_from_slice(&network_outputs[2..2 + memory_channels]);
+
+                KernelOutput::from_network_outputs(network_outputs)
+            })
+            .collect()
+    }
 
-        output
+    /// Makes sure there's memory for every position of the board, forgetting it if the board changed size.
+    fn fit_memory(&mut self, game: &Game) {
+        let board_size = (game.board.width, game.board.height);
+        let position_count = board_size.0 * board_size.1;
+        let recurrent_value_count = position_count * self.compiled_network.recurrent_node_count();
+        let channel_value_count = position_count * self.config.memory_channels;
+
+        if self.memory.board_size != board_size
+            || self.memory.recurrent_values.len() != recurrent_value_count
+            || self.memory.channel_values.len() != channel_value_count
+        {
+            self.memory = PlayerMemory {
+                board_size,
+                channel_values: vec![0.0; channel_value_count],
+                recurrent_values: vec![0.0; recurrent_value_count],
+            };
+        }
     }
 
-    fn get_kernel(&self, game: &Game, center_pos: Position) -> Kernel {
-        let kernel_radius = (self.config.kernel_diameter / 2) as isize;
+    /// Computes the features of the whole board which the kernels of this config need.
+    fn get_board_features(&self, game: &Game) -> BoardFeatures {
+        let population = self.config.context_features.population.then(|| {
+            game.count_cells(TileState::Alive) as Value / game.board.tiles.len().max(1) as Value
+        });
 
-        let relative_positions =
-            (-kernel_radius..=kernel_radius).cartesian_product(-kernel_radius..=kernel_radius);
+        // The preview ticks a copy of just the board with its own RNG, since a clone of the game's RNG would let
+        // the player see exactly how noisy rules are going to play out, which it shouldn't be able to know.
+        let preview = (self.config.preview_ticks > 0).then(|| {
+            let mut preview_game = Game::new(game.board.clone(), game.rule.clone());
+            preview_game.topology = game.topology;
 
-        let positions = relative_positions.map(|(rel_x, rel_y)| {
-            Some(Position {
-                x: (center_pos.x as isize)
-                    .checked_add(rel_x)
-                    .filter(|val| val.is_positive())? as usize,
-                y: (center_pos.y as isize)
-                    .checked_add(rel_y)
-                    .filter(|val| val.is_positive())? as usize,
-            })
+            // SAFETY: The rule map already fits the board of the game, which has the same size,
+            //         so unwrap should always be OK.
+            preview_game.set_rule_map(game.rule_map().cloned()).unwrap();
+
+            for _ in 0..self.config.preview_ticks {
+                preview_game.tick();
+            }
+
+            preview_game.board
         });
 
+        let neighbour_counts = self.config.use_neighbour_counts.then(|| {
+            game.board
+                .enumerate_tiles()
+                .map(|(position, _)| {
+                    game.board
+                        .neighbourhood(position, 1, game.topology)
+                        .filter(|(offset, neighbour_position)| {
+                            (offset.x, offset.y) != (0, 0)
+                                && neighbour_position.and_then(|position| game.board.tile(position))
+                                    == Some(&TileState::Alive)
+                        })
+                        .count() as u8
+                })
+                .collect_vec()
+        });
+
+        BoardFeatures {
+            population,
+            preview,
+            neighbour_counts,
+        }
+    }
+
+    /// Gets the kernel centered on the position, along with the parts of the board features it covers.
+    fn get_kernel(
+        &self,
+        game: &Game,
+        center_pos: Position,
+        board_features: &BoardFeatures,
+    ) -> Kernel {
+        let kernel_radius = self.config.kernel_diameter / 2;
+
+        // The network sees the edges of the board the same way the rules do.
+        let positions = game
+            .board
+            .neighbourhood(center_pos, kernel_radius, game.topology)
+            .map(|(_, position)| position)
+            .collect_vec();
+
         let tiles = positions
-            .map(|maybe_position| game.board.tile(maybe_position?).copied())
+            .iter()
+            .map(|maybe_position| game.board.tile((*maybe_position)?).copied())
             .collect_vec();
 
-        Kernel { tiles }
+        let ages = self.config.use_tile_ages.then(|| {
+            positions
+                .iter()
+                .map(|maybe_position| {
+                    maybe_position
+                        .and_then(|position| game.board.tile_age(position))
+                        .unwrap_or(0)
+                })
+                .collect_vec()
+        });
+
+        let memory_channels = self.config.memory_channels;
+        let memory = (memory_channels > 0).then(|| {
+            let mut memory = Vec::with_capacity(positions.len() * memory_channels);
+
+            for maybe_position in &positions {
+                match maybe_position {
+                    Some(position) => memory.extend_from_slice(
+                        &self.memory.channel_values
+                            [self.memory.position_range(*position, memory_channels)],
+                    ),
+                    None => memory.extend(iter::repeat_n(0.0, memory_channels)),
+                }
+            }
+
+            memory
+        });
+
+        Kernel {
+            tiles,
+            ages,
+            memory,
+            context: self.get_context(game, center_pos, board_features.population),
+            preview: board_features.preview.as_ref().map(|preview| {
+                positions
+                    .iter()
+                    .map(|maybe_position| preview.tile((*maybe_position)?).copied())
+                    .collect_vec()
+            }),
+            history: (self.config.history_depth > 0).then(|| {
+                (0..self.config.history_depth)
+                    .flat_map(|depth| {
+                        // Until enough moves have been made, the oldest board known stands in for the missing ones.
+                        let board = self
+                            .history
+                            .get(depth)
+                            .or(self.history.back())
+                            .unwrap_or(&game.board);
+
+                        positions
+                            .iter()
+                            .map(|maybe_position| board.tile((*maybe_position)?).copied())
+                    })
+                    .collect_vec()
+            }),
+            neighbour_counts: board_features
+                .neighbour_counts
+                .as_ref()
+                .map(|neighbour_counts| {
+                    positions
+                        .iter()
+                        .map(|maybe_position| {
+                            maybe_position.map_or(0, |position| {
+                                neighbour_counts[position.x + position.y * game.board.width]
+                            })
+                        })
+                        .collect_vec()
+                }),
+        }
+    }
+
+    fn get_context(
+        &self,
+        game: &Game,
+        center_pos: Position,
+        population: Option<Value>,
+    ) -> KernelContext {
+        let features = self.config.context_features;
+        let (width, height) = (game.board.width, game.board.height);
+
+        let coordinates = features.coordinates.then(|| {
+            [
+                center_pos.x as Value / width.saturating_sub(1).max(1) as Value,
+                center_pos.y as Value / height.saturating_sub(1).max(1) as Value,
+            ]
+        });
+
+        let edge_distance = features.edge_distance.then(|| match game.topology {
+            Topology::Bounded => {
+                let distance = [
+                    center_pos.x,
+                    width - 1 - center_pos.x,
+                    center_pos.y,
+                    height - 1 - center_pos.y,
+                ]
+                .into_iter()
+                .min()
+                .unwrap_or(0);
+
+                let max_distance = (width.min(height).saturating_sub(1) / 2).max(1);
+                distance as Value / max_distance as Value
+            }
+            Topology::Toroidal => 1.0,
+        });
+
+        let region_populations = (0..features.region_population_scales)
+            .map(|scale| {
+                let region_radius = self.config.kernel_diameter << scale;
+
+                let (alive_count, tile_count) = game
+                    .board
+                    .neighbourhood(center_pos, region_radius, game.topology)
+                    .filter_map(|(_, position)| game.board.tile(position?))
+                    .fold((0, 0), |(alive_count, tile_count), tile| {
+                        (
+                            alive_count + usize::from(*tile == TileState::Alive),
+                            tile_count + 1,
+                        )
+                    });
+
+                alive_count as Value / tile_count.max(1) as Value
+            })
+            .collect();
+
+        KernelContext {
+            population,
+            rounds_left: features
+                .rounds_left
+                .then_some(self.rounds_left.unwrap_or(0.0)),
+            coordinates,
+            edge_distance,
+            region_populations,
+        }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub(super) struct KernelOutput {
+pub struct KernelOutput {
     /// The score by which selecting the position should be preferred.
     pub score: f32,
 
     // A value representing how much the network wants the tile to be alive.
     pub state: f32,
 }
+
+impl KernelOutput {
+    fn from_network_outputs(network_outputs: &[Value]) -> Self {
+        Self {
+            score: network_outputs[0],
+            state: network_outputs[1],
+        }
+    }
+}
+
+/// Picks the top position by highest score.
+fn best_scored_position<I>(scored_positions: I) -> Option<(Position, KernelOutput)>
+where
+    I: IntoIterator<Item = (Position, KernelOutput)>,
+{
+    scored_positions.into_iter().max_by(
+        |(_, KernelOutput { score: a_score, .. }), (_, KernelOutput { score: b_score, .. })| {
+            // Due to NaNs or something Rust doesn't provide Ord implementations for floats so gotta do with this.
+            if a_score > b_score {
+                Ordering::Greater
+            } else if a_score == b_score {
+                Ordering::Equal
+            } else {
+                Ordering::Less
+            }
+        },
+    )
+}
+
+/// The move the output for a position asks for, if it wants to change the tile at all.
+fn chosen_move(game: &Game, position: Position, output: KernelOutput) -> Option<NetworkPlayerMove> {
+    // SAFETY: Players don't let networks give arbitrary positions,
+    //         so positions will always correspond to a tile.
+    let current_state = *game.board.tile(position).unwrap();
+
+    let wanted_state = match output.state {
+        ..-0.5 => Some(TileState::Dead),
+        0.5.. => Some(TileState::Alive),
+        _ => None,
+    };
+
+    if let Some(wanted_state) = wanted_state
+        && wanted_state != current_state
+    {
+        Some(NetworkPlayerMove {
+            new_state: wanted_state,
+            position,
+        })
+    } else {
+        None
+    }
+}
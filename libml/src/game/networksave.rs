@@ -1,35 +1,200 @@
-use std::{fs, path::Path};
+use std::{fmt, fs, path::Path};
 
-use anyhow::Context;
-use serde::{Deserialize, Serialize};
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
-use crate::network::Network;
+use crate::network::{
+    ActivationPlacement, Network, NetworkConfig,
+    conv::ConvNetwork,
+    functions::{Activator, Combinator},
+};
 
-use super::NetworkPlayerConfig;
+use super::{NetworkPlayerConfig, conv::ConvNetworkPlayerConfig};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The version of the save format written by this version of the library.
+/// - 0: The network config isn't stored, the saves don't have a version field at all.
+/// - 1: The network config is stored with the network.
+/// - 2: The input layer doesn't store the last input values.
+/// - 3: Inputs can skip layers and nodes can be recurrent, which older versions would silently drop.
+///   Nodes, inputs and the network also store innovation numbers.
+pub const CURRENT_SAVE_VERSION: u32 = 3;
+
+#[derive(Debug, Clone)]
 pub struct NetworkSave {
     pub player_config: NetworkPlayerConfig,
-
-    #[serde(with = "base64_msgpack")]
     pub network: Network,
 }
 
+/// A save of a network for a ConvNetworkPlayer, stored the same way as NetworkSave.
+/// Convolutional networks were added in format version 3, so there are no older saves to migrate.
+#[derive(Debug, Clone)]
+pub struct ConvNetworkSave {
+    pub player_config: ConvNetworkPlayerConfig,
+    pub network: ConvNetwork,
+}
+
+/// How to load saves of older format versions.
+#[derive(Debug, Clone, Copy)]
+pub struct MigrationOptions {
+    /// The config to give networks of saves which don't store one.
+    pub assumed_network_config: NetworkConfig,
+}
+
+impl Default for MigrationOptions {
+    fn default() -> Self {
+        Self {
+            // What the trainer used to create networks with, back when the config wasn't saved.
+            assumed_network_config: NetworkConfig {
+                activator: Activator::ReLU,
+                combinator: Combinator::Mul,
+                activation_placement: ActivationPlacement::ActivateInputs,
+            },
+        }
+    }
+}
+
+/// Something that had to be assumed to load a save of an older format version,
+/// which is up to the caller to report.
+#[derive(Debug, Clone, Copy)]
+pub enum MigrationWarning {
+    /// The save doesn't store the network config, so the assumed one was given to the network.
+    AssumedNetworkConfig { version: u32, config: NetworkConfig },
+}
+
+impl fmt::Display for MigrationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AssumedNetworkConfig { version, config } => write!(
+                f,
+                "The network save is of format version {} which doesn't store the network config, assuming {:?}",
+                version, config
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedNetworkSave<C, N> {
+    #[serde(default)]
+    version: u32,
+    player_config: C,
+
+    #[serde(
+        with = "base64_msgpack",
+        bound(serialize = "N: Serialize", deserialize = "N: DeserializeOwned")
+    )]
+    network: N,
+}
+
+/// Just the version of a save, to know what to deserialize the rest as.
+#[derive(Deserialize)]
+struct SaveVersion {
+    #[serde(default)]
+    version: u32,
+}
+
 impl NetworkSave {
     pub fn save<P>(&self, path: P) -> anyhow::Result<()>
     where
         P: AsRef<Path>,
     {
-        let save_data_serialized =
-            serde_json::to_string_pretty(&self).context("Couldn't serialize network save")?;
+        write_save(path.as_ref(), self.player_config, &self.network)
+    }
 
-        let path = path.as_ref();
-        let parent_path = path.parent().context("No parent path")?;
+    /// Loads a save, along with the warnings about anything that had to be assumed to migrate it.
+    pub fn load<P>(path: P) -> anyhow::Result<(Self, Vec<MigrationWarning>)>
+    where
+        P: AsRef<Path>,
+    {
+        Self::load_with_options(path, &MigrationOptions::default())
+    }
 
-        let _ = fs::create_dir_all(parent_path);
-        fs::write(path, save_data_serialized).context("Couldn't write network save")?;
+    pub fn load_with_options<P>(
+        path: P,
+        options: &MigrationOptions,
+    ) -> anyhow::Result<(Self, Vec<MigrationWarning>)>
+    where
+        P: AsRef<Path>,
+    {
+        let save_serialized = fs::read(path).context("Couldn't read network save")?;
+        Self::from_slice_with_options(&save_serialized, options)
+    }
 
-        Ok(())
+    /// Deserializes a save from the contents of a save file, see load.
+    pub fn from_slice(save_serialized: &[u8]) -> anyhow::Result<(Self, Vec<MigrationWarning>)> {
+        Self::from_slice_with_options(save_serialized, &MigrationOptions::default())
+    }
+
+    pub fn from_slice_with_options(
+        save_serialized: &[u8],
+        options: &MigrationOptions,
+    ) -> anyhow::Result<(Self, Vec<MigrationWarning>)> {
+        let SaveVersion { version } = serde_json::from_slice(save_serialized)
+            .context("Couldn't deserialize network save version")?;
+
+        let mut warnings = Vec::new();
+
+        let save = match version {
+            0 => {
+                let save: SerializedNetworkSave<NetworkPlayerConfig, legacy::NetworkV0> =
+                    deserialize_save(save_serialized)?;
+
+                let network = save.network.migrate(options.assumed_network_config);
+
+                warnings.push(MigrationWarning::AssumedNetworkConfig {
+                    version,
+                    config: network.config,
+                });
+
+                Self {
+                    player_config: save.player_config,
+                    network,
+                }
+            }
+
+            1 => {
+                let save: SerializedNetworkSave<NetworkPlayerConfig, legacy::NetworkV1> =
+                    deserialize_save(save_serialized)?;
+
+                Self {
+                    player_config: save.player_config,
+                    network: save.network.migrate(),
+                }
+            }
+
+            // Version 2 saves only lack fields which default to what they meant before.
+            2 | CURRENT_SAVE_VERSION => {
+                let save: SerializedNetworkSave<NetworkPlayerConfig, Network> =
+                    deserialize_save(save_serialized)?;
+
+                Self {
+                    player_config: save.player_config,
+                    network: save.network,
+                }
+            }
+
+            _ => bail!(
+                "Network save format version {} is newer than the supported version {}",
+                version,
+                CURRENT_SAVE_VERSION
+            ),
+        };
+
+        // Better to fail here than panic in the middle of a game due to a hand edited or corrupted save.
+        save.player_config
+            .validate_network(&save.network)
+            .context("Invalid network save")?;
+
+        Ok((save, warnings))
+    }
+}
+
+impl ConvNetworkSave {
+    pub fn save<P>(&self, path: P) -> anyhow::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        write_save(path.as_ref(), self.player_config, &self.network)
     }
 
     pub fn load<P>(path: P) -> anyhow::Result<Self>
@@ -37,9 +202,136 @@ impl NetworkSave {
         P: AsRef<Path>,
     {
         let save_serialized = fs::read(path).context("Couldn't read network save")?;
-        let save = serde_json::from_slice(&save_serialized)
-            .context("Couldn't deserialize network save")?;
-        Ok(save)
+        Self::from_slice(&save_serialized)
+    }
+
+    /// Deserializes a save from the contents of a save file, see load.
+    pub fn from_slice(save_serialized: &[u8]) -> anyhow::Result<Self> {
+        let SaveVersion { version } = serde_json::from_slice(save_serialized)
+            .context("Couldn't deserialize network save version")?;
+
+        match version {
+            CURRENT_SAVE_VERSION => {}
+            _ if version > CURRENT_SAVE_VERSION => bail!(
+                "Network save format version {} is newer than the supported version {}",
+                version,
+                CURRENT_SAVE_VERSION
+            ),
+            _ => bail!(
+                "Network save format version {} predates convolutional networks, so it can't be of one",
+                version
+            ),
+        }
+
+        let save: SerializedNetworkSave<ConvNetworkPlayerConfig, ConvNetwork> =
+            deserialize_save(save_serialized)?;
+
+        save.player_config
+            .validate_network(&save.network)
+            .context("Invalid network save")?;
+
+        Ok(Self {
+            player_config: save.player_config,
+            network: save.network,
+        })
+    }
+}
+
+fn write_save<C, N>(path: &Path, player_config: C, network: &N) -> anyhow::Result<()>
+where
+    C: Serialize,
+    N: Serialize,
+{
+    let save_data = SerializedNetworkSave {
+        version: CURRENT_SAVE_VERSION,
+        player_config,
+        network,
+    };
+
+    let save_data_serialized =
+        serde_json::to_string_pretty(&save_data).context("Couldn't serialize network save")?;
+
+    let parent_path = path.parent().context("No parent path")?;
+
+    let _ = fs::create_dir_all(parent_path);
+    fs::write(path, save_data_serialized).context("Couldn't write network save")?;
+
+    Ok(())
+}
+
+fn deserialize_save<C, N>(save_serialized: &[u8]) -> anyhow::Result<SerializedNetworkSave<C, N>>
+where
+    C: DeserializeOwned,
+    N: DeserializeOwned,
+{
+    serde_json::from_slice(save_serialized).context("Couldn't deserialize network save")
+}
+
+mod legacy {
+    use serde::{Deserialize, de::IgnoredAny};
+
+    use crate::network::{
+        ActivationPlacement, Network, NetworkConfig,
+        layer::{compute::ComputeLayer, input::InputLayer},
+    };
+
+    /// The input layer used to store the last input values, which are just dropped now.
+    #[derive(Deserialize)]
+    struct InputLayerV1 {
+        height: usize,
+        _output_values: IgnoredAny,
+    }
+
+    impl InputLayerV1 {
+        fn migrate(self) -> InputLayer {
+            InputLayer::new(self.height)
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct NetworkV0 {
+        input_layer: InputLayerV1,
+        compute_layers: Vec<ComputeLayer>,
+
+        /// Only stored by the last saves of this version.
+        #[serde(default)]
+        activation_placement: Option<ActivationPlacement>,
+    }
+
+    impl NetworkV0 {
+        pub fn migrate(self, assumed_config: NetworkConfig) -> Network {
+            let config = NetworkConfig {
+                activation_placement: self
+                    .activation_placement
+                    .unwrap_or(assumed_config.activation_placement),
+                ..assumed_config
+            };
+
+            Network {
+                config,
+                input_layer: self.input_layer.migrate(),
+                compute_layers: self.compute_layers,
+                next_innovation: 0,
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct NetworkV1 {
+        config: NetworkConfig,
+        input_layer: InputLayerV1,
+        compute_layers: Vec<ComputeLayer>,
+    }
+
+    impl NetworkV1 {
+        pub fn migrate(self) -> Network {
+            Network {
+                config: self.config,
+                input_layer: self.input_layer.migrate(),
+                compute_layers: self.compute_layers,
+                next_innovation: 0,
+            }
+        }
     }
 }
 
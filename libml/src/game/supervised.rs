@@ -0,0 +1,124 @@
+use itertools::Itertools;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::network::{
+    Network, Value,
+    gradient::{Gradients, NonDifferentiableError},
+    harness::NetworkHarness,
+    optimizer::Optimizer,
+};
+
+use super::{NetworkPlayerConfig, kernel::Kernel};
+
+/// What the network should output for a kernel, see KernelOutput.
+/// Outputs without a target are left alone, so labels can cover just the score or just the state.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct KernelTarget {
+    pub score: Option<Value>,
+    pub state: Option<Value>,
+}
+
+/// A labeled kernel, e.g. from a solver or a heuristic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KernelSample {
+    pub kernel: Kernel,
+    pub target: KernelTarget,
+}
+
+/// Trains networks with backpropagation to output the targets of labeled kernels,
+/// so that they can be pretrained before being evolved further.
+pub struct SupervisedTrainer<O> {
+    pub player_config: NetworkPlayerConfig,
+    pub optimizer: O,
+
+    /// How many samples the gradients are averaged over for each step of the optimizer.
+    pub batch_size: usize,
+}
+
+impl<O> SupervisedTrainer<O>
+where
+    O: Optimizer,
+{
+    pub fn new(player_config: NetworkPlayerConfig, optimizer: O) -> Self {
+        Self {
+            player_config,
+            optimizer,
+            batch_size: 32,
+        }
+    }
+
+    /// Goes through every sample once in a random order, returning the average loss of the samples before training on them.
+    pub fn train_epoch(
+        &mut self,
+        network: &mut Network,
+        samples: &[KernelSample],
+    ) -> Result<Value, NonDifferentiableError> {
+        network.check_differentiable()?;
+        assert!(self.batch_size > 0, "Batch size must be positive");
+
+        let mut inputs = self.input_values(network, samples);
+        inputs.shuffle(&mut rand::rng());
+
+        let mut gradients = Gradients::zeros(network);
+        let mut total_loss = 0.0;
+
+        for batch in inputs.chunks(self.batch_size) {
+            gradients.clear();
+
+            for (input_values, target) in batch {
+                let forward_pass = network.forward_pass(input_values);
+                let (loss, output_gradients) = loss(forward_pass.outputs(), target);
+
+                // Averaging over the batch keeps the step size independent of the batch size.
+                let output_gradients =
+                    output_gradients.map(|gradient| gradient / batch.len() as Value);
+
+                network.backpropagate(&forward_pass, &output_gradients, &mut gradients);
+                total_loss += loss;
+            }
+
+            self.optimizer.step(network, &gradients);
+        }
+
+        Ok(total_loss / samples.len().max(1) as Value)
+    }
+
+    /// The average loss of the network over the samples, without training it.
+    pub fn loss(&self, network: &Network, samples: &[KernelSample]) -> Value {
+        let total_loss: Value = self
+            .input_values(network, samples)
+            .iter()
+            .map(|(input_values, target)| loss(&network.compute(input_values), target).0)
+            .sum();
+
+        total_loss / samples.len().max(1) as Value
+    }
+
+    fn input_values<'a>(
+        &self,
+        network: &Network,
+        samples: &'a [KernelSample],
+    ) -> Vec<(Vec<Value>, &'a KernelTarget)> {
+        let harness =
+            NetworkHarness::new(network).with_inputs(Kernel::input_providers(&self.player_config));
+
+        samples
+            .iter()
+            .map(|sample| (harness.input_values(&sample.kernel), &sample.target))
+            .collect_vec()
+    }
+}
+
+/// Half of the squared error of the outputs which have a target, along with its gradients for the score and state outputs.
+fn loss(outputs: &[Value], target: &KernelTarget) -> (Value, [Value; 2]) {
+    assert!(outputs.len() >= 2, "Not enough outputs in kernel network");
+
+    let errors = [
+        target.score.map_or(0.0, |score| outputs[0] - score),
+        target.state.map_or(0.0, |state| outputs[1] - state),
+    ];
+
+    let loss = errors.iter().map(|error| error * error / 2.0).sum();
+    (loss, errors)
+}
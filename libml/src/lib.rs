@@ -1,4 +1,4 @@
-#![feature(trait_alias, impl_trait_in_bindings, let_chains)]
+#![feature(trait_alias, impl_trait_in_bindings, let_chains, portable_simd)]
 
 pub mod game;
 pub mod network;
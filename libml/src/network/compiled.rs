@@ -0,0 +1,891 @@
+use std::{array, iter, simd::Simd};
+
+use itertools::Itertools;
+
+use super::{
+    ActivationPlacement, Network, Value,
+    functions::{Activator, Combinator},
+    layer::{compute::ComputeLayer, dense::DenseLayer},
+};
+
+/// The minimum share of possible inputs a layer needs to have to be computed as a matrix.
+const DENSE_LAYER_MIN_DENSITY: f32 = 0.5;
+
+/// How many samples are computed at once by the dense layer kernel.
+const SAMPLE_LANES: usize = 8;
+
+/// How many nodes are computed at once by the dense layer kernel, reusing each loaded input for all of them.
+const NODE_BLOCK_SIZE: usize = 4;
+
+type SampleVector = Simd<Value, SAMPLE_LANES>;
+
+/// A network flattened for fast inference of many inputs at once.
+/// Computes exactly the same outputs as the network it was compiled from, but doesn't follow later changes to it.
+#[derive(Debug, Clone)]
+pub struct CompiledNetwork {
+    activation_placement: ActivationPlacement,
+    input_count: usize,
+    layers: Vec<CompiledLayer>,
+
+    /// The number of values computed for each sample, see InferenceBuffers.
+    value_count: usize,
+
+    /// Where the value of each recurrent node is, in the order of the nodes.
+    recurrent_node_indices: Vec<usize>,
+}
+
+/// The nodes of a compute layer as a structure of arrays.
+#[derive(Debug, Clone)]
+struct CompiledLayer {
+    /// The index of the first node of the layer among the values of a sample, see InferenceBuffers.
+    first_node_index: usize,
+
+    connections: Connections,
+    input_counts: Vec<usize>,
+
+    biases: Vec<Value>,
+    activators: Vec<Activator>,
+    combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Clone)]
+enum Connections {
+    /// The inputs of all nodes in a single list.
+    Sparse {
+        /// Where the inputs of each node start in the input lists, with an extra entry for the end of the last node.
+        node_input_starts: Vec<usize>,
+
+        /// Indices among the values of a sample, so that skip connections and recurrent inputs work the same as any other input.
+        source_indices: Vec<usize>,
+        weights: Vec<Value>,
+    },
+
+    /// A weight matrix with one row per node, see DenseLayer.
+    /// Only used for layers of summing nodes, which can be computed as a matrix multiplication.
+    Dense {
+        /// The index of the first node of the previous layer among the values of a sample.
+        first_source_index: usize,
+        row_length: usize,
+        weights: Vec<Value>,
+        mask: Vec<bool>,
+    },
+}
+
+impl CompiledLayer {
+    fn height(&self) -> usize {
+        self.biases.len()
+    }
+}
+
+/// Reusable memory for computing compiled networks, so that nothing needs to be allocated once it's warmed up.
+#[derive(Debug, Clone, Default)]
+pub struct InferenceBuffers {
+    /// The previous values of the recurrent nodes followed by the values of every layer,
+    /// since skip connections can reach back to any of them.
+    /// Stored node by node with the values of every sample next to each other.
+    node_values: Vec<Value>,
+}
+
+impl CompiledNetwork {
+    /// Panics if a node has an input from a node which doesn't exist, see `Network::validate`.
+    pub fn compile(network: &Network) -> Self {
+        let input_count = network.input_layer.height();
+        let recurrent_node_count = network.recurrent_node_count();
+
+        // Where the nodes of each layer start among the values of a sample, with an extra entry for the end.
+        // The previous recurrent values come first, so that they're before every layer the same way as sources are.
+        let mut layer_first_node_indices = vec![recurrent_node_count];
+        for layer in network.layers() {
+            // SAFETY: Starts with an entry.
+            let first_node_index = *layer_first_node_indices.last().unwrap();
+            layer_first_node_indices.push(first_node_index + layer.output_node_indices().len());
+        }
+
+        let mut recurrent_node_indices = Vec::with_capacity(recurrent_node_count);
+
+        let layers = network
+            .compute_layers
+            .iter()
+            .enumerate()
+            .map(|(comp_layer_index, comp_layer)| {
+                Self::compile_layer(
+                    network,
+                    comp_layer,
+                    comp_layer_index + 1,
+                    &layer_first_node_indices,
+                    &mut recurrent_node_indices,
+                )
+            })
+            .collect();
+
+        Self {
+            activation_placement: network.config.activation_placement,
+            input_count,
+            layers,
+            // SAFETY: There's always an entry for the end.
+            value_count: *layer_first_node_indices.last().unwrap(),
+            recurrent_node_indices,
+        }
+    }
+
+    /// Compiles a layer, adding its recurrent nodes to the ones of the earlier layers.
+    fn compile_layer(
+        network: &Network,
+        comp_layer: &ComputeLayer,
+        layer_index: usize,
+        layer_first_node_indices: &[usize],
+        recurrent_node_indices: &mut Vec<usize>,
+    ) -> CompiledLayer {
+        let previous_layer_height =
+            layer_first_node_indices[layer_index] - layer_first_node_indices[layer_index - 1];
+
+        let layer_config = network
+            .config
+            .with_overrides(comp_layer.activator, comp_layer.combinator);
+
+        let node_configs = comp_layer
+            .nodes
+            .iter()
+            .map(|node| layer_config.with_overrides(node.activator, node.combinator))
+            .collect_vec();
+
+        let connections = Self::dense_connections(
+            network,
+            comp_layer,
+            layer_first_node_indices[layer_index - 1],
+            previous_layer_height,
+            node_configs.iter().map(|config| config.combinator),
+        )
+        .unwrap_or_else(|| {
+            let mut node_input_starts = vec![0];
+            let mut source_indices = Vec::new();
+            let mut weights = Vec::new();
+
+            for (node_index, node) in comp_layer.nodes.iter().enumerate() {
+                for input in &node.inputs {
+                    let source_layer_index = input
+                        .source_layer_index(layer_index)
+                        .expect("Missing input layer");
+                    let source_layer_first_node_index =
+                        layer_first_node_indices[source_layer_index];
+
+                    assert!(
+                        source_layer_first_node_index + input.node_index
+                            < layer_first_node_indices[source_layer_index + 1],
+                        "Missing input"
+                    );
+
+                    source_indices.push(source_layer_first_node_index + input.node_index);
+                    weights.push(input.weight);
+                }
+
+                // The previous value of a recurrent node is its last input, same as in Node::compute.
+                if let Some(recurrent_weight) = node.recurrent_weight {
+                    source_indices.push(recurrent_node_indices.len());
+                    weights.push(recurrent_weight);
+                    recurrent_node_indices.push(layer_first_node_indices[layer_index] + node_index);
+                }
+
+                node_input_starts.push(source_indices.len());
+            }
+
+            Connections::Sparse {
+                node_input_starts,
+                source_indices,
+                weights,
+            }
+        });
+
+        CompiledLayer {
+            first_node_index: layer_first_node_indices[layer_index],
+            connections,
+            input_counts: comp_layer
+                .nodes
+                .iter()
+                .map(|node| node.inputs.len() + node.is_recurrent() as usize)
+                .collect(),
+            biases: comp_layer.nodes.iter().map(|node| node.bias).collect(),
+            activators: node_configs.iter().map(|config| config.activator).collect(),
+            combinators: node_configs
+                .iter()
+                .map(|config| config.combinator)
+                .collect(),
+        }
+    }
+
+    /// The connections of the layer as a matrix, if it's dense enough and the result would be exactly the same.
+    fn dense_connections<I>(
+        network: &Network,
+        comp_layer: &ComputeLayer,
+        previous_layer_first_node_index: usize,
+        previous_layer_height: usize,
+        mut combinators: I,
+    ) -> Option<Connections>
+    where
+        I: Iterator<Item = Combinator>,
+    {
+        let possible_input_count = comp_layer.nodes.len() * previous_layer_height;
+        let input_count: usize = comp_layer.nodes.iter().map(|node| node.inputs.len()).sum();
+
+        let is_dense = possible_input_count > 0
+            && input_count as f32 / possible_input_count as f32 >= DENSE_LAYER_MIN_DENSITY;
+
+        let is_summing = network.config.activation_placement == ActivationPlacement::ActivateSums
+            && combinators
+                .all(|combinator| matches!(combinator, Combinator::Add | Combinator::Mean));
+
+        // The matrix sums the inputs in the order of the previous layer, which only rounds the same way if the nodes do too.
+        let is_ordered = comp_layer.nodes.iter().all(|node| {
+            node.inputs
+                .iter()
+                .tuple_windows()
+                .all(|(a, b)| a.node_index < b.node_index)
+        });
+
+        if !(is_dense && is_summing && is_ordered) {
+            return None;
+        }
+
+        let dense_layer = DenseLayer::from_sparse(comp_layer, previous_layer_height).ok()?;
+        let (weights, mask) = dense_layer.matrix();
+
+        Some(Connections::Dense {
+            first_source_index: previous_layer_first_node_index,
+            row_length: dense_layer.input_count(),
+            weights: weights.to_vec(),
+            mask: mask.to_vec(),
+        })
+    }
+
+    pub fn input_count(&self) -> usize {
+        self.input_count
+    }
+
+    pub fn output_count(&self) -> usize {
+        self.layers
+            .last()
+            .map_or(self.input_count, |layer| layer.height())
+    }
+
+    /// How many values each sample needs to remember for the next computation, see `compute_batch_recurrent`.
+    pub fn recurrent_node_count(&self) -> usize {
+        self.recurrent_node_indices.len()
+    }
+
+    /// The index of the first output node among the values of a sample.
+    fn first_output_node_index(&self) -> usize {
+        self.layers
+            .last()
+            .map_or(self.recurrent_node_count(), |layer| layer.first_node_index)
+    }
+
+    /// Computes the outputs for a batch of inputs, given one sample after another.
+    /// The outputs are written the same way, replacing any previous contents.
+    /// Recurrent nodes start from zero for every sample, as with `Network::compute`.
+    pub fn compute_batch(
+        &self,
+        inputs: &[Value],
+        outputs: &mut Vec<Value>,
+        buffers: &mut InferenceBuffers,
+    ) {
+        self.compute_batch_with(inputs, None, outputs, buffers);
+    }
+
+    /// Computes a batch like `compute_batch`, given the previous values of the recurrent nodes of each sample
+    /// one sample after another. They're replaced with the new values, as with `Network::compute_recurrent`.
+    pub fn compute_batch_recurrent(
+        &self,
+        inputs: &[Value],
+        recurrent_values: &mut [Value],
+        outputs: &mut Vec<Value>,
+        buffers: &mut InferenceBuffers,
+    ) {
+        self.compute_batch_with(inputs, Some(recurrent_values), outputs, buffers);
+    }
+
+    fn compute_batch_with(
+        &self,
+        inputs: &[Value],
+        mut recurrent_values: Option<&mut [Value]>,
+        outputs: &mut Vec<Value>,
+        buffers: &mut InferenceBuffers,
+    ) {
+        assert!(
+            self.input_count > 0 && inputs.len().is_multiple_of(self.input_count),
+            "Input count isn't a multiple of the network input count ({})",
+            self.input_count
+        );
+
+        let sample_count = inputs.len() / self.input_count;
+        let recurrent_node_count = self.recurrent_node_count();
+
+        if let Some(recurrent_values) = &recurrent_values {
+            assert_eq!(
+                recurrent_values.len(),
+                sample_count * recurrent_node_count,
+                "Recurrent values don't match the batch"
+            );
+        }
+
+        // Transpose the recurrent values and inputs so that the values of each node are next to each other.
+        buffers.node_values.clear();
+        for recurrent_index in 0..recurrent_node_count {
+            match &recurrent_values {
+                Some(recurrent_values) => buffers.node_values.extend(
+                    recurrent_values
+                        .iter()
+                        .skip(recurrent_index)
+                        .step_by(recurrent_node_count),
+                ),
+                None => buffers
+                    .node_values
+                    .extend(iter::repeat_n(0.0, sample_count)),
+            }
+        }
+
+        for input_index in 0..self.input_count {
+            buffers
+                .node_values
+                .extend(inputs.iter().skip(input_index).step_by(self.input_count));
+        }
+
+        buffers
+            .node_values
+            .resize(self.value_count * sample_count, 0.0);
+
+        for layer in &self.layers {
+            let (earlier_layer_values, layer_values) = buffers
+                .node_values
+                .split_at_mut(layer.first_node_index * sample_count);
+            let layer_values = &mut layer_values[..layer.height() * sample_count];
+
+            match &layer.connections {
+                Connections::Sparse {
+                    node_input_starts,
+                    source_indices,
+                    weights,
+                } => {
+                    for node_index in 0..layer.height() {
+                        let node_values = &mut layer_values
+                            [node_index * sample_count..(node_index + 1) * sample_count];
+                        let input_range =
+                            node_input_starts[node_index]..node_input_starts[node_index + 1];
+
+                        self.combine_sparse_node(
+                            layer,
+                            node_index,
+                            &source_indices[input_range.clone()],
+                            &weights[input_range],
+                            earlier_layer_values,
+                            node_values,
+                        );
+                    }
+                }
+                Connections::Dense {
+                    first_source_index,
+                    row_length,
+                    weights,
+                    mask,
+                } => sum_dense_nodes(
+                    layer,
+                    *row_length,
+                    weights,
+                    mask,
+                    sample_count,
+                    &earlier_layer_values[first_source_index * sample_count..],
+                    layer_values,
+                ),
+            }
+
+            for node_index in 0..layer.height() {
+                let node_values =
+                    &mut layer_values[node_index * sample_count..(node_index + 1) * sample_count];
+
+                self.finish_node(layer, node_index, node_values);
+            }
+        }
+
+        if let Some(recurrent_values) = &mut recurrent_values {
+            for (recurrent_index, node_index) in self.recurrent_node_indices.iter().enumerate() {
+                let node_values =
+                    &buffers.node_values[node_index * sample_count..(node_index + 1) * sample_count];
+
+                for (sample_index, node_value) in node_values.iter().enumerate() {
+                    recurrent_values[sample_index * recurrent_node_count + recurrent_index] =
+                        *node_value;
+                }
+            }
+        }
+
+        // And transpose back.
+        let output_count = self.output_count();
+        let output_values = &buffers.node_values[self.first_output_node_index() * sample_count..];
+        outputs.clear();
+        for sample_index in 0..sample_count {
+            outputs.extend(
+                output_values
+                    .iter()
+                    .skip(sample_index)
+                    .step_by(sample_count)
+                    .take(output_count),
+            );
+        }
+    }
+
+    /// Combines the inputs of a node for every sample, following the exact order of operations of Node::compute.
+    fn combine_sparse_node(
+        &self,
+        layer: &CompiledLayer,
+        node_index: usize,
+        source_indices: &[usize],
+        weights: &[Value],
+        earlier_layer_values: &[Value],
+        node_values: &mut [Value],
+    ) {
+        let sample_count = node_values.len();
+
+        let activator = layer.activators[node_index];
+        let combinator = layer.combinators[node_index];
+
+        for (nth_input, (source_index, weight)) in source_indices.iter().zip(weights).enumerate() {
+            let source_values = earlier_layer_values
+                .get(source_index * sample_count..(source_index + 1) * sample_count)
+                .expect("Missing input");
+
+            // The mean is a sum starting from zero, everything else starts from the first value.
+            let is_first = nth_input == 0 && combinator != Combinator::Mean;
+
+            // Matching outside of the loops lets the compiler vectorize them.
+            match self.activation_placement {
+                ActivationPlacement::ActivateInputs => {
+                    combine_into(combinator, is_first, node_values, source_values, |value| {
+                        activator.activate(value * weight)
+                    })
+                }
+                ActivationPlacement::ActivateSums => {
+                    combine_into(combinator, is_first, node_values, source_values, |value| {
+                        value * weight
+                    })
+                }
+            }
+        }
+    }
+
+    /// Turns the combined inputs of a node into its outputs for every sample.
+    fn finish_node(&self, layer: &CompiledLayer, node_index: usize, node_values: &mut [Value]) {
+        let activator = layer.activators[node_index];
+        let combinator = layer.combinators[node_index];
+        let bias = layer.biases[node_index];
+        let input_count = layer.input_counts[node_index];
+
+        for node_value in node_values.iter_mut() {
+            // Nodes without inputs are left at zero, same as with the mean.
+            if combinator == Combinator::Mean && input_count > 0 {
+                *node_value /= input_count as Value;
+            }
+
+            *node_value = match self.activation_placement {
+                ActivationPlacement::ActivateInputs => *node_value + bias,
+                ActivationPlacement::ActivateSums => activator.activate(*node_value + bias),
+            };
+        }
+    }
+}
+
+/// Sums the weighted inputs of the nodes of a dense layer for every sample.
+fn sum_dense_nodes(
+    layer: &CompiledLayer,
+    row_length: usize,
+    weights: &[Value],
+    mask: &[bool],
+    sample_count: usize,
+    previous_layer_values: &[Value],
+    layer_values: &mut [Value],
+) {
+    assert!(
+        previous_layer_values.len() >= row_length * sample_count,
+        "Missing input"
+    );
+
+    let dense_layer = DenseLayerView {
+        layer,
+        row_length,
+        weights,
+        mask,
+        sample_count,
+        previous_layer_values,
+    };
+
+    let full_block_end = layer.height() - layer.height() % NODE_BLOCK_SIZE;
+
+    for block_start in (0..full_block_end).step_by(NODE_BLOCK_SIZE) {
+        dense_layer.sum_node_block::<NODE_BLOCK_SIZE>(block_start, layer_values);
+    }
+
+    for node_index in full_block_end..layer.height() {
+        dense_layer.sum_node_block::<1>(node_index, layer_values);
+    }
+}
+
+struct DenseLayerView<'a> {
+    layer: &'a CompiledLayer,
+    row_length: usize,
+    weights: &'a [Value],
+    mask: &'a [bool],
+    sample_count: usize,
+    previous_layer_values: &'a [Value],
+}
+
+impl DenseLayerView<'_> {
+    /// Sums the weighted inputs of N nodes, a few samples at a time so that every loaded input is used for all of them.
+    fn sum_node_block<const N: usize>(&self, block_start: usize, layer_values: &mut [Value]) {
+        let rows = block_start * self.row_length..(block_start + N) * self.row_length;
+        let weight_rows: [&[Value]; N] = array::from_fn(|nth_node| {
+            &self.weights[(block_start + nth_node) * self.row_length..][..self.row_length]
+        });
+        let mask_rows: [&[bool]; N] = array::from_fn(|nth_node| {
+            &self.mask[(block_start + nth_node) * self.row_length..][..self.row_length]
+        });
+
+        // Adding to a negative zero never changes a value, unlike adding to a positive zero which turns negative
+        // zeros positive. Sums start from it to match the ones starting from their first value in Node::compute.
+        let initial_sums: [SampleVector; N] = array::from_fn(|nth_node| {
+            let node_index = block_start + nth_node;
+
+            if self.layer.combinators[node_index] == Combinator::Add
+                && self.layer.input_counts[node_index] > 0
+            {
+                SampleVector::splat(-0.0)
+            } else {
+                SampleVector::splat(0.0)
+            }
+        });
+
+        // Fully connected nodes are common enough to be worth skipping the mask for.
+        let is_fully_connected = self.mask[rows].iter().all(|is_input| *is_input);
+
+        for sample_start in (0..self.sample_count).step_by(SAMPLE_LANES) {
+            let sums = if is_fully_connected {
+                self.sum_sample_chunk::<N, false>(
+                    initial_sums,
+                    weight_rows,
+                    mask_rows,
+                    sample_start,
+                )
+            } else {
+                self.sum_sample_chunk::<N, true>(initial_sums, weight_rows, mask_rows, sample_start)
+            };
+
+            let lane_count = SAMPLE_LANES.min(self.sample_count - sample_start);
+            for (nth_node, sum) in sums.iter().enumerate() {
+                let values_start = (block_start + nth_node) * self.sample_count + sample_start;
+                layer_values[values_start..values_start + lane_count]
+                    .copy_from_slice(&sum.as_array()[..lane_count]);
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn sum_sample_chunk<const N: usize, const MASKED: bool>(
+        &self,
+        mut sums: [SampleVector; N],
+        weight_rows: [&[Value]; N],
+        mask_rows: [&[bool]; N],
+        sample_start: usize,
+    ) -> [SampleVector; N] {
+        let lane_count = SAMPLE_LANES.min(self.sample_count - sample_start);
+
+        for source_index in 0..self.row_length {
+            let source_start = source_index * self.sample_count + sample_start;
+            let source_values =
+                &self.previous_layer_values[source_start..source_start + lane_count];
+
+            // Loading a full vector directly is much faster than padding a partial one.
+            let source_values = if lane_count == SAMPLE_LANES {
+                SampleVector::from_slice(source_values)
+            } else {
+                SampleVector::load_or_default(source_values)
+            };
+
+            for nth_node in 0..N {
+                if !MASKED || mask_rows[nth_node][source_index] {
+                    sums[nth_node] +=
+                        source_values * SampleVector::splat(weight_rows[nth_node][source_index]);
+                }
+            }
+        }
+
+        sums
+    }
+}
+
+/// Combines the weighted source values into the node values, or just sets them for the first input.
+fn combine_into<F>(
+    combinator: Combinator,
+    is_first: bool,
+    node_values: &mut [Value],
+    source_values: &[Value],
+    weighted_value: F,
+) where
+    F: Fn(Value) -> Value,
+{
+    let values = node_values.iter_mut().zip(source_values);
+
+    if is_first {
+        values.for_each(|(node_value, source_value)| *node_value = weighted_value(*source_value));
+        return;
+    }
+
+    match combinator {
+        Combinator::Add | Combinator::Mean => values
+            .for_each(|(node_value, source_value)| *node_value += weighted_value(*source_value)),
+        Combinator::Mul => values
+            .for_each(|(node_value, source_value)| *node_value *= weighted_value(*source_value)),
+        Combinator::Max => values.for_each(|(node_value, source_value)| {
+            *node_value = node_value.max(weighted_value(*source_value))
+        }),
+        Combinator::Min => values.for_each(|(node_value, source_value)| {
+            *node_value = node_value.min(weighted_value(*source_value))
+        }),
+    }
+}
+
+impl Network {
+    /// Compiles the network for batched inference, see CompiledNetwork.
+    pub fn compile(&self) -> CompiledNetwork {
+        CompiledNetwork::compile(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+    use strum::IntoEnumIterator;
+
+    use crate::network::{NetworkConfig, node::NodeInput};
+
+    use super::*;
+
+    const BATCH_SIZES: [usize; 4] = [1, SAMPLE_LANES - 1, SAMPLE_LANES, SAMPLE_LANES * 2 + 3];
+
+    /// Creates a network with random biases and no connections, the activator and combinator being random
+    /// unless the network is meant to be computed densely.
+    fn random_network(rng: &mut StdRng, dense: bool) -> Network {
+        let config = if dense {
+            NetworkConfig {
+                activator: *Activator::iter().collect_vec().choose(rng).unwrap(),
+                combinator: Combinator::Add,
+                activation_placement: ActivationPlacement::ActivateSums,
+            }
+        } else {
+            NetworkConfig {
+                activator: *Activator::iter().collect_vec().choose(rng).unwrap(),
+                combinator: *Combinator::iter().collect_vec().choose(rng).unwrap(),
+                activation_placement: *[
+                    ActivationPlacement::ActivateInputs,
+                    ActivationPlacement::ActivateSums,
+                ]
+                .choose(rng)
+                .unwrap(),
+            }
+        };
+
+        let mut network = Network::new(
+            config,
+            rng.random_range(1..8),
+            rng.random_range(1..4),
+            rng.random_range(1..8),
+            rng.random_range(1..4),
+        );
+
+        for node in network
+            .compute_layers
+            .iter_mut()
+            .flat_map(|layer| &mut layer.nodes)
+        {
+            node.bias = rng.random_range(-1.0..1.0);
+        }
+
+        network
+    }
+
+    /// Gives every node inputs from random nodes of earlier layers, at most the given number of layers back.
+    fn connect_randomly(rng: &mut StdRng, network: &mut Network, max_skipped_layers: usize) {
+        let layer_heights = network
+            .layers()
+            .map(|layer| layer.output_node_indices().len())
+            .collect_vec();
+
+        for (comp_layer_index, layer) in network.compute_layers.iter_mut().enumerate() {
+            let layer_index = comp_layer_index + 1;
+
+            for node in &mut layer.nodes {
+                for _ in 0..rng.random_range(0..4) {
+                    let skipped_layers =
+                        rng.random_range(0..=max_skipped_layers.min(layer_index - 1));
+                    let source_height = layer_heights[layer_index - 1 - skipped_layers];
+
+                    node.inputs.push(NodeInput {
+                        skipped_layers,
+                        ..NodeInput::new(
+                            rng.random_range(0..source_height),
+                            rng.random_range(-1.0..1.0),
+                        )
+                    });
+                }
+            }
+        }
+    }
+
+    fn random_inputs(rng: &mut StdRng, count: usize) -> Vec<Value> {
+        (0..count).map(|_| rng.random_range(-1.5..1.5)).collect()
+    }
+
+    /// The values have to be exactly the same, NaN included.
+    fn assert_same_values(expected: &[Value], actual: &[Value]) {
+        let same = expected.len() == actual.len()
+            && expected.iter().zip(actual).all(|(expected, actual)| {
+                expected.to_bits() == actual.to_bits() || (expected.is_nan() && actual.is_nan())
+            });
+
+        assert!(same, "Expected {:?}, got {:?}", expected, actual);
+    }
+
+    fn assert_batches_match(rng: &mut StdRng, network: &Network, compiled: &CompiledNetwork) {
+        let input_count = network.input_layer.height();
+        let mut outputs = Vec::new();
+        let mut buffers = InferenceBuffers::default();
+
+        for batch_size in BATCH_SIZES {
+            let inputs = random_inputs(rng, batch_size * input_count);
+            compiled.compute_batch(&inputs, &mut outputs, &mut buffers);
+
+            let expected_outputs = inputs
+                .chunks(input_count)
+                .flat_map(|sample_inputs| network.compute(sample_inputs))
+                .collect_vec();
+
+            assert_same_values(&expected_outputs, &outputs);
+        }
+    }
+
+    #[test]
+    fn sparse_layers_match_network() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..200 {
+            let mut network = random_network(&mut rng, false);
+            connect_randomly(&mut rng, &mut network, 0);
+
+            let compiled = network.compile();
+            assert_batches_match(&mut rng, &network, &compiled);
+        }
+    }
+
+    #[test]
+    fn dense_layers_match_network() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..200 {
+            let mut network = random_network(&mut rng, true);
+
+            let mut previous_height = network.input_layer.height();
+            for layer in &mut network.compute_layers {
+                for node in &mut layer.nodes {
+                    node.inputs = (0..previous_height)
+                        .map(|source_node_index| {
+                            NodeInput::new(source_node_index, rng.random_range(-1.0..1.0))
+                        })
+                        .collect();
+                }
+
+                previous_height = layer.nodes.len();
+            }
+
+            let compiled = network.compile();
+            assert!(
+                compiled
+                    .layers
+                    .iter()
+                    .all(|layer| matches!(layer.connections, Connections::Dense { .. })),
+                "Fully connected summing layers weren't compiled as dense"
+            );
+
+            assert_batches_match(&mut rng, &network, &compiled);
+        }
+    }
+
+    #[test]
+    fn skip_connections_match_network() {
+        let mut rng = StdRng::seed_from_u64(2);
+
+        for _ in 0..200 {
+            let mut network = random_network(&mut rng, false);
+            connect_randomly(&mut rng, &mut network, usize::MAX);
+
+            let compiled = network.compile();
+            assert_batches_match(&mut rng, &network, &compiled);
+        }
+    }
+
+    #[test]
+    fn recurrent_nodes_match_network() {
+        let mut rng = StdRng::seed_from_u64(3);
+
+        for _ in 0..200 {
+            let mut network = random_network(&mut rng, false);
+            connect_randomly(&mut rng, &mut network, usize::MAX);
+
+            for node in network
+                .compute_layers
+                .iter_mut()
+                .flat_map(|layer| &mut layer.nodes)
+            {
+                if rng.random_bool(0.3) {
+                    node.recurrent_weight = Some(rng.random_range(-1.0..1.0));
+                }
+            }
+
+            let compiled = network.compile();
+            let input_count = network.input_layer.height();
+            let recurrent_node_count = network.recurrent_node_count();
+            assert_eq!(compiled.recurrent_node_count(), recurrent_node_count);
+
+            // Without the previous values, the recurrent nodes start from zero.
+            assert_batches_match(&mut rng, &network, &compiled);
+
+            let batch_size = *BATCH_SIZES.choose(&mut rng).unwrap();
+            let mut expected_recurrent_values = vec![0.0; batch_size * recurrent_node_count];
+            let mut recurrent_values = expected_recurrent_values.clone();
+            let mut outputs = Vec::new();
+            let mut buffers = InferenceBuffers::default();
+
+            // A few steps, so that the remembered values feed back in.
+            for _ in 0..4 {
+                let inputs = random_inputs(&mut rng, batch_size * input_count);
+                compiled.compute_batch_recurrent(
+                    &inputs,
+                    &mut recurrent_values,
+                    &mut outputs,
+                    &mut buffers,
+                );
+
+                // NOTE: Indexed instead of chunked, since there might not be any recurrent values to chunk.
+                let expected_outputs = (0..batch_size)
+                    .flat_map(|sample_index| {
+                        let sample_recurrent_values = &mut expected_recurrent_values
+                            [sample_index * recurrent_node_count..][..recurrent_node_count];
+
+                        network.compute_recurrent(
+                            &inputs[sample_index * input_count..][..input_count],
+                            sample_recurrent_values,
+                        )
+                    })
+                    .collect_vec();
+
+                assert_same_values(&expected_outputs, &outputs);
+                assert_same_values(&expected_recurrent_values, &recurrent_values);
+            }
+        }
+    }
+}
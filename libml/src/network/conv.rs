@@ -0,0 +1,170 @@
+use std::{borrow::Cow, mem};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    functions::Activator,
+    layer::conv::{ConvLayer, FeatureMap, Padding},
+    validation::{ValidationError, ValidationErrors},
+};
+
+/// A network of convolutional layers, which maps a whole feature map to another of the same size in one pass.
+/// Every layer sees further than the one before it, so later layers can pick up on structure larger than a kernel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConvNetwork {
+    /// Used by the layers which don't override it.
+    pub activator: Activator,
+    pub layers: Vec<ConvLayer>,
+}
+
+impl ConvNetwork {
+    /// Creates a network with zero weights and biases, with every layer using the same kernel diameter and padding.
+    pub fn new(
+        activator: Activator,
+        kernel_diameter: usize,
+        padding: Padding,
+        input_channels: usize,
+        hidden_layer_count: usize,
+        hidden_layer_channels: usize,
+        output_channels: usize,
+    ) -> Self {
+        let layer_channels = (0..hidden_layer_count)
+            .map(|_| hidden_layer_channels)
+            .chain([output_channels]);
+
+        let layers = layer_channels
+            .scan(input_channels, |previous_channels, channels| {
+                let layer = ConvLayer::new(kernel_diameter, *previous_channels, channels, padding);
+                *previous_channels = channels;
+                Some(layer)
+            })
+            .collect();
+
+        Self { activator, layers }
+    }
+
+    pub fn input_channels(&self) -> usize {
+        self.layers
+            .first()
+            .map_or(0, |layer| layer.input_channels())
+    }
+
+    pub fn output_channels(&self) -> usize {
+        self.layers
+            .last()
+            .map_or(0, |layer| layer.output_channels())
+    }
+
+    /// Computes the output map for the given input map, which has to have the input channels of the first layer.
+    pub fn compute(&self, inputs: &FeatureMap) -> FeatureMap {
+        let Some((first_layer, later_layers)) = self.layers.split_first() else {
+            return inputs.clone();
+        };
+
+        let mut values = first_layer.compute(self.activator, inputs);
+
+        // The layers take turns writing into the two maps, so computing doesn't allocate past the first layers.
+        let mut spare_values = FeatureMap::zeros(0, 0, 0);
+        for layer in later_layers {
+            layer.compute_into(self.activator, &values, &mut spare_values);
+            mem::swap(&mut values, &mut spare_values);
+        }
+
+        values
+    }
+
+    /// Checks the structure of the network, so that it can be computed without panicking.
+    /// As with other networks, the input map counts as layer 0 and each output channel of a layer as a node.
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        ValidationErrors::into_result(self.find_validation_errors())
+    }
+
+    pub(crate) fn find_validation_errors(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        if self.layers.is_empty() {
+            errors.push(ValidationError::NoComputeLayers);
+        }
+
+        for (conv_layer_index, layer) in self.layers.iter().enumerate() {
+            let layer_index = conv_layer_index + 1;
+
+            if layer.kernel_diameter() % 2 == 0 {
+                errors.push(ValidationError::EvenKernelDiameter {
+                    layer_index,
+                    kernel_diameter: layer.kernel_diameter(),
+                });
+            }
+
+            if let Some(previous_layer) = conv_layer_index
+                .checked_sub(1)
+                .map(|previous_index| &self.layers[previous_index])
+                && previous_layer.output_channels() != layer.input_channels()
+            {
+                errors.push(ValidationError::ChannelCountMismatch {
+                    layer_index,
+                    expected: previous_layer.output_channels(),
+                    actual: layer.input_channels(),
+                });
+            }
+
+            let parameter_count = layer.parameters().count();
+            if parameter_count != layer.expected_parameter_count() {
+                errors.push(ValidationError::ParameterCountMismatch {
+                    layer_index,
+                    expected: layer.expected_parameter_count(),
+                    actual: parameter_count,
+                });
+
+                // The weights can't be told apart from the biases without the right amount of them.
+                continue;
+            }
+
+            let kernel_size = layer.kernel_diameter().pow(2);
+            for output_channel in 0..layer.output_channels() {
+                for kernel_index in 0..kernel_size {
+                    for input_channel in 0..layer.input_channels() {
+                        let weight = layer.weight(
+                            output_channel,
+                            kernel_index / layer.kernel_diameter(),
+                            kernel_index % layer.kernel_diameter(),
+                            input_channel,
+                        );
+
+                        if !weight.is_finite() {
+                            errors.push(ValidationError::NonFiniteWeight {
+                                layer_index,
+                                node_index: output_channel,
+                                source_node_index: input_channel,
+                                weight,
+                            });
+                        }
+                    }
+                }
+
+                let bias = layer.bias(output_channel);
+                if !bias.is_finite() {
+                    errors.push(ValidationError::NonFiniteBias {
+                        layer_index,
+                        node_index: output_channel,
+                        bias,
+                    });
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+impl<'a> From<&'a ConvNetwork> for Cow<'a, ConvNetwork> {
+    fn from(network: &'a ConvNetwork) -> Self {
+        Cow::Borrowed(network)
+    }
+}
+
+impl From<ConvNetwork> for Cow<'_, ConvNetwork> {
+    fn from(network: ConvNetwork) -> Self {
+        Cow::Owned(network)
+    }
+}
@@ -1,30 +1,92 @@
 use serde::{Deserialize, Serialize};
-use strum::EnumString;
+use strum::{EnumIter, EnumString};
 
 use super::Value;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumString, EnumIter)]
 #[strum(serialize_all = "snake_case")]
 pub enum Combinator {
     Add,
     Mul,
+    Max,
+    Min,
+    Mean,
 }
 
 impl Combinator {
-    pub fn combine(&self, a: Value, b: Value) -> Value {
+    /// Combines all of the values into one, returning None if there are no values.
+    pub fn combine_all<I>(&self, values: I) -> Option<Value>
+    where
+        I: IntoIterator<Item = Value>,
+    {
+        let values = values.into_iter();
+
         match self {
-            Combinator::Add => a + b,
-            Combinator::Mul => a * b,
+            Combinator::Add => values.reduce(|a, b| a + b),
+            Combinator::Mul => values.reduce(|a, b| a * b),
+            Combinator::Max => values.reduce(Value::max),
+            Combinator::Min => values.reduce(Value::min),
+            Combinator::Mean => {
+                let (sum, count) =
+                    values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+                (count > 0).then(|| sum / count as Value)
+            }
+        }
+    }
+
+    /// The partial derivatives of the combined value with respect to each of the values.
+    /// The maximum and minimum only pass gradients to the value they picked.
+    pub fn partial_derivatives(&self, values: &[Value]) -> Vec<Value> {
+        match self {
+            Combinator::Add => vec![1.0; values.len()],
+            Combinator::Mean => vec![1.0 / values.len() as Value; values.len()],
+            Combinator::Mul => {
+                // The product of all the other values, without dividing so that zeros work too.
+                let mut partials = vec![1.0; values.len()];
+
+                let mut prefix_product = 1.0;
+                for (partial, value) in partials.iter_mut().zip(values) {
+                    *partial = prefix_product;
+                    prefix_product *= value;
+                }
+
+                let mut suffix_product = 1.0;
+                for (partial, value) in partials.iter_mut().zip(values).rev() {
+                    *partial *= suffix_product;
+                    suffix_product *= value;
+                }
+
+                partials
+            }
+            Combinator::Max | Combinator::Min => {
+                let mut partials = vec![0.0; values.len()];
+
+                let picked_value = self.combine_all(values.iter().copied());
+                let picked_index = values
+                    .iter()
+                    .position(|value| Some(*value) == picked_value);
+
+                if let Some(picked_index) = picked_index {
+                    partials[picked_index] = 1.0;
+                }
+
+                partials
+            }
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumString, EnumIter)]
 #[strum(serialize_all = "snake_case")]
 pub enum Activator {
     Binary,
     ReLU,
     Tanh,
+    Sigmoid,
+    LeakyReLU,
+    Identity,
+    Gaussian,
+    Sine,
 }
 
 impl Activator {
@@ -33,6 +95,33 @@ impl Activator {
             Activator::Binary => if value > 0.5 { 1.0 } else { 0.0 },
             Activator::ReLU => value.max(0.0),
             Activator::Tanh => value.tanh(),
+            Activator::Sigmoid => 1.0 / (1.0 + (-value).exp()),
+            Activator::LeakyReLU => if value > 0.0 { value } else { value * 0.01 },
+            Activator::Identity => value,
+            Activator::Gaussian => (-value * value).exp(),
+            Activator::Sine => value.sin(),
+        }
+    }
+
+    /// Whether the activator has a derivative useful for backpropagation.
+    pub fn is_differentiable(&self) -> bool {
+        !matches!(self, Activator::Binary)
+    }
+
+    /// The derivative of the activator at the value, zero for activators which aren't differentiable.
+    pub fn derivative(&self, value: Value) -> Value {
+        match self {
+            Activator::Binary => 0.0,
+            Activator::ReLU => if value > 0.0 { 1.0 } else { 0.0 },
+            Activator::Tanh => 1.0 - value.tanh().powi(2),
+            Activator::Sigmoid => {
+                let activated = self.activate(value);
+                activated * (1.0 - activated)
+            }
+            Activator::LeakyReLU => if value > 0.0 { 1.0 } else { 0.01 },
+            Activator::Identity => 1.0,
+            Activator::Gaussian => -2.0 * value * (-value * value).exp(),
+            Activator::Sine => value.cos(),
         }
     }
 }
@@ -0,0 +1,446 @@
+use std::fmt;
+
+use super::{
+    ActivationPlacement, Network, Value, functions::Activator, layer::compute::ComputeLayer,
+};
+
+/// The gradients of a loss with respect to the parameters of a network, in the order of `Network::parameters`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Gradients(pub Vec<Value>);
+
+impl Gradients {
+    pub fn zeros(network: &Network) -> Self {
+        Self(vec![0.0; network.parameter_count()])
+    }
+
+    pub fn clear(&mut self) {
+        self.0.fill(0.0);
+    }
+}
+
+/// Why a network can't be backpropagated through.
+/// Layer indices include the input layer, same as with validation errors.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NonDifferentiableError {
+    /// A node uses an activator without a usable derivative.
+    Activator {
+        layer_index: usize,
+        node_index: usize,
+        activator: Activator,
+    },
+
+    /// A node is recurrent, which the forward pass can't follow from one computation to the next.
+    Recurrent {
+        layer_index: usize,
+        node_index: usize,
+    },
+}
+
+impl fmt::Display for NonDifferentiableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Activator {
+                layer_index,
+                node_index,
+                activator,
+            } => write!(
+                f,
+                "Node {} of layer {} uses the {:?} activator which isn't differentiable",
+                node_index, layer_index, activator
+            ),
+            Self::Recurrent {
+                layer_index,
+                node_index,
+            } => write!(
+                f,
+                "Node {} of layer {} is recurrent, which can't be backpropagated through",
+                node_index, layer_index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NonDifferentiableError {}
+
+/// The values of every layer from computing a network, which are needed to backpropagate through it.
+#[derive(Debug, Clone)]
+pub struct ForwardPass {
+    /// Starting from the input layer, any of which can be the source of inputs of the later layers.
+    layer_values: Vec<Vec<Value>>,
+}
+
+impl ForwardPass {
+    pub fn outputs(&self) -> &[Value] {
+        // SAFETY: There's always at least the input layer.
+        self.layer_values.last().unwrap()
+    }
+}
+
+impl Network {
+    /// The number of trainable parameters, which are the weights of the inputs of each node followed by its bias.
+    /// Recurrent weights aren't included, as recurrent networks can't be trained by backpropagation.
+    pub fn parameter_count(&self) -> usize {
+        self.compute_layers
+            .iter()
+            .flat_map(|comp_layer| &comp_layer.nodes)
+            .map(|node| node.inputs.len() + 1)
+            .sum()
+    }
+
+    pub fn parameters(&self) -> impl Iterator<Item = Value> {
+        self.compute_layers
+            .iter()
+            .flat_map(|comp_layer| &comp_layer.nodes)
+            .flat_map(|node| {
+                node.inputs
+                    .iter()
+                    .map(|input| input.weight)
+                    .chain([node.bias])
+            })
+    }
+
+    pub fn parameters_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.compute_layers
+            .iter_mut()
+            .flat_map(|comp_layer| &mut comp_layer.nodes)
+            .flat_map(|node| {
+                node.inputs
+                    .iter_mut()
+                    .map(|input| &mut input.weight)
+                    .chain([&mut node.bias])
+            })
+    }
+
+    /// Checks that every node of the network can be backpropagated through.
+    pub fn check_differentiable(&self) -> Result<(), NonDifferentiableError> {
+        for (comp_layer_index, comp_layer) in self.compute_layers.iter().enumerate() {
+            let layer_config = self
+                .config
+                .with_overrides(comp_layer.activator, comp_layer.combinator);
+
+            for (node_index, node) in comp_layer.nodes.iter().enumerate() {
+                let node_config = layer_config.with_overrides(node.activator, node.combinator);
+
+                if !node_config.activator.is_differentiable() {
+                    return Err(NonDifferentiableError::Activator {
+                        layer_index: comp_layer_index + 1,
+                        node_index,
+                        activator: node_config.activator,
+                    });
+                }
+
+                if node.is_recurrent() {
+                    return Err(NonDifferentiableError::Recurrent {
+                        layer_index: comp_layer_index + 1,
+                        node_index,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Computes the network the same way as `compute`, but keeps the values of every layer for backpropagation.
+    /// Recurrent nodes are fed zeros, which is why `check_differentiable` rejects networks having any.
+    pub fn forward_pass(&self, inputs: &[Value]) -> ForwardPass {
+        let mut recurrent_values = vec![0.0; self.recurrent_node_count()];
+
+        ForwardPass {
+            layer_values: self.compute_layer_values(inputs, &mut recurrent_values),
+        }
+    }
+
+    /// Adds the gradients of a loss with respect to the parameters to the given gradients,
+    /// given the gradients of the loss with respect to the outputs of the forward pass.
+    /// Outputs without a given gradient don't affect the loss.
+    pub fn backpropagate(
+        &self,
+        forward_pass: &ForwardPass,
+        output_gradients: &[Value],
+        gradients: &mut Gradients,
+    ) {
+        assert_eq!(
+            gradients.0.len(),
+            self.parameter_count(),
+            "Gradients don't match the network"
+        );
+
+        let output_count = forward_pass.outputs().len();
+        assert!(
+            output_gradients.len() <= output_count,
+            "Too many output gradients for the {} outputs",
+            output_count
+        );
+
+        // The gradients of the outputs of every layer, which skip connections can add to from any later layer.
+        let mut value_gradients = forward_pass
+            .layer_values
+            .iter()
+            .map(|values| vec![0.0; values.len()])
+            .collect::<Vec<_>>();
+
+        // SAFETY: There's always at least the input layer.
+        value_gradients.last_mut().unwrap()[..output_gradients.len()]
+            .copy_from_slice(output_gradients);
+
+        // Where the parameters of each compute layer start.
+        let layer_parameter_starts = self
+            .compute_layers
+            .iter()
+            .scan(0, |parameter_start, comp_layer| {
+                let layer_parameter_start = *parameter_start;
+                *parameter_start += comp_layer
+                    .nodes
+                    .iter()
+                    .map(|node| node.inputs.len() + 1)
+                    .sum::<usize>();
+                Some(layer_parameter_start)
+            })
+            .collect::<Vec<_>>();
+
+        for (comp_layer_index, comp_layer) in self.compute_layers.iter().enumerate().rev() {
+            let layer_index = comp_layer_index + 1;
+            let (previous_layer_gradients, layer_gradients) =
+                value_gradients.split_at_mut(layer_index);
+
+            self.backpropagate_layer(
+                comp_layer,
+                &forward_pass.layer_values[..layer_index],
+                &layer_gradients[0],
+                previous_layer_gradients,
+                &mut gradients.0[layer_parameter_starts[comp_layer_index]..],
+            );
+        }
+    }
+
+    /// Adds the gradients of the parameters of the layer, and the gradients of the values of the earlier layers.
+    fn backpropagate_layer(
+        &self,
+        comp_layer: &ComputeLayer,
+        previous_layer_values: &[Vec<Value>],
+        node_gradients: &[Value],
+        previous_layer_gradients: &mut [Vec<Value>],
+        parameter_gradients: &mut [Value],
+    ) {
+        let layer_config = self
+            .config
+            .with_overrides(comp_layer.activator, comp_layer.combinator);
+
+        let layer_index = previous_layer_values.len();
+        let mut parameter_index = 0;
+
+        for (node, node_gradient) in comp_layer.nodes.iter().zip(node_gradients) {
+            let config = layer_config.with_overrides(node.activator, node.combinator);
+
+            // The recurrent input of the forward pass is always zero, but it still takes part in combining.
+            let weighted_values = node
+                .inputs
+                .iter()
+                .map(|input| input.source_value(previous_layer_values) * input.weight)
+                .chain(
+                    node.recurrent_weight
+                        .map(|recurrent_weight| 0.0 * recurrent_weight),
+                )
+                .collect::<Vec<_>>();
+
+            // The gradients of the weighted values and of the bias.
+            let (weighted_gradients, bias_gradient) = match config.activation_placement {
+                ActivationPlacement::ActivateInputs => {
+                    let activated_values = weighted_values
+                        .iter()
+                        .map(|value| config.activator.activate(*value))
+                        .collect::<Vec<_>>();
+
+                    let weighted_gradients = config
+                        .combinator
+                        .partial_derivatives(&activated_values)
+                        .into_iter()
+                        .zip(&weighted_values)
+                        .map(|(partial, value)| {
+                            node_gradient * partial * config.activator.derivative(*value)
+                        })
+                        .collect::<Vec<_>>();
+
+                    (weighted_gradients, *node_gradient)
+                }
+                ActivationPlacement::ActivateSums => {
+                    let combined_value = config
+                        .combinator
+                        .combine_all(weighted_values.iter().copied())
+                        .unwrap_or(0.0);
+                    let sum_gradient =
+                        node_gradient * config.activator.derivative(combined_value + node.bias);
+
+                    let weighted_gradients = config
+                        .combinator
+                        .partial_derivatives(&weighted_values)
+                        .into_iter()
+                        .map(|partial| sum_gradient * partial)
+                        .collect::<Vec<_>>();
+
+                    (weighted_gradients, sum_gradient)
+                }
+            };
+
+            for (input, weighted_gradient) in node.inputs.iter().zip(weighted_gradients) {
+                // SAFETY: The source value was already found when computing the weighted values.
+                let source_layer_index = input.source_layer_index(layer_index).unwrap();
+
+                parameter_gradients[parameter_index] +=
+                    weighted_gradient * input.source_value(previous_layer_values);
+                previous_layer_gradients[source_layer_index][input.node_index] +=
+                    weighted_gradient * input.weight;
+                parameter_index += 1;
+            }
+
+            parameter_gradients[parameter_index] += bias_gradient;
+            parameter_index += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use crate::network::{NetworkConfig, functions::Combinator, node::NodeInput};
+
+    use super::*;
+
+    /// Activators which are smooth everywhere, so that finite differences don't straddle a kink.
+    const SMOOTH_ACTIVATORS: [Activator; 5] = [
+        Activator::Tanh,
+        Activator::Sigmoid,
+        Activator::Identity,
+        Activator::Gaussian,
+        Activator::Sine,
+    ];
+
+    const SMOOTH_COMBINATORS: [Combinator; 3] =
+        [Combinator::Add, Combinator::Mean, Combinator::Mul];
+
+    const EPSILON: Value = 1e-2;
+    const TOLERANCE: Value = 1e-2;
+
+    /// A fully connected network with random weights and biases, with a skip connection to every earlier layer.
+    fn random_network(rng: &mut StdRng, config: NetworkConfig) -> Network {
+        let mut network = Network::new(config, 3, 2, 3, 2);
+
+        let layer_heights = network
+            .compute_layers
+            .iter()
+            .map(|comp_layer| comp_layer.nodes.len())
+            .collect::<Vec<_>>();
+
+        for (comp_layer_index, comp_layer) in network.compute_layers.iter_mut().enumerate() {
+            let previous_height = comp_layer_index
+                .checked_sub(1)
+                .map_or(3, |previous_index| layer_heights[previous_index]);
+
+            for node in &mut comp_layer.nodes {
+                for source_node_index in 0..previous_height {
+                    node.inputs.push(NodeInput::new(
+                        source_node_index,
+                        rng.random_range(-1.0..1.0),
+                    ));
+                }
+
+                for skipped_layers in 1..=comp_layer_index {
+                    node.inputs.push(NodeInput {
+                        skipped_layers,
+                        ..NodeInput::new(0, rng.random_range(-1.0..1.0))
+                    });
+                }
+
+                node.bias = rng.random_range(-1.0..1.0);
+            }
+        }
+
+        network
+    }
+
+    /// The loss is a weighted sum of the outputs, the weights being the gradients of the outputs.
+    fn loss(network: &Network, inputs: &[Value], output_gradients: &[Value]) -> Value {
+        network
+            .compute(inputs)
+            .iter()
+            .zip(output_gradients)
+            .map(|(output, gradient)| output * gradient)
+            .sum()
+    }
+
+    #[test]
+    fn backpropagation_matches_finite_differences() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for activation_placement in [
+            ActivationPlacement::ActivateInputs,
+            ActivationPlacement::ActivateSums,
+        ] {
+            for activator in SMOOTH_ACTIVATORS {
+                for combinator in SMOOTH_COMBINATORS {
+                    let config = NetworkConfig {
+                        activator,
+                        combinator,
+                        activation_placement,
+                    };
+
+                    let mut network = random_network(&mut rng, config);
+                    network.check_differentiable().unwrap();
+
+                    let inputs = [0.3, -0.7, 0.9];
+                    let output_gradients = [0.6, -1.1];
+
+                    let mut gradients = Gradients::zeros(&network);
+                    let forward_pass = network.forward_pass(&inputs);
+                    network.backpropagate(&forward_pass, &output_gradients, &mut gradients);
+
+                    for parameter_index in 0..network.parameter_count() {
+                        let original = network.parameters().nth(parameter_index).unwrap();
+                        let mut loss_with = |parameter| {
+                            *network.parameters_mut().nth(parameter_index).unwrap() = parameter;
+                            loss(&network, &inputs, &output_gradients)
+                        };
+
+                        let numeric_gradient = (loss_with(original + EPSILON)
+                            - loss_with(original - EPSILON))
+                            / (2.0 * EPSILON);
+                        loss_with(original);
+
+                        let gradient = gradients.0[parameter_index];
+                        assert!(
+                            (gradient - numeric_gradient).abs()
+                                <= TOLERANCE * numeric_gradient.abs().max(1.0),
+                            "Parameter {} of a network with {:?}: backpropagated {}, finite difference {}",
+                            parameter_index,
+                            config,
+                            gradient,
+                            numeric_gradient
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn recurrent_networks_are_rejected() {
+        let config = NetworkConfig {
+            activator: Activator::Tanh,
+            ..NetworkConfig::default()
+        };
+
+        let mut network = random_network(&mut StdRng::seed_from_u64(0), config);
+        network.check_differentiable().unwrap();
+
+        network.compute_layers[1].nodes[2].recurrent_weight = Some(0.5);
+        assert_eq!(
+            network.check_differentiable(),
+            Err(NonDifferentiableError::Recurrent {
+                layer_index: 2,
+                node_index: 2,
+            })
+        );
+    }
+}
@@ -1,16 +1,22 @@
+use std::borrow::Cow;
+
 use super::{Network, Value};
 
-pub trait InputProvider<S> = Fn(&S) -> Value;
+pub trait InputProvider<S> = Fn(&S) -> Value + Send + Sync;
 
 pub struct NetworkHarness<'a, S> {
-    pub network: &'a mut Network,
+    /// Either borrowed or owned, so that the harness can be kept around without the network.
+    pub network: Cow<'a, Network>,
     pub input_providers: Vec<Box<dyn InputProvider<S>>>,
 }
 
 impl<'a, S> NetworkHarness<'a, S> {
-    pub fn new(network: &'a mut Network) -> Self {
+    pub fn new<N>(network: N) -> Self
+    where
+        N: Into<Cow<'a, Network>>,
+    {
         Self {
-            network,
+            network: network.into(),
             input_providers: Vec::new(),
         }
     }
@@ -49,20 +55,36 @@ impl<'a, S> NetworkHarness<'a, S> {
         self.input_providers.push(provider);
     }
 
-    pub fn compute(&mut self, state: &S) -> impl Iterator<Item = Value> {
-        self.update_inputs(state);
-
+    pub fn compute(&self, state: &S) -> impl Iterator<Item = Value> {
         self.network
-            .compute()
+            .compute(&self.input_values(state))
             .into_iter()
     }
 
-    fn update_inputs(&mut self, state: &S) {
-        let input_values = self
-            .input_providers
-            .iter()
-            .map(|input_provider| input_provider(state));
+    /// Appends the inputs for the state to a batch, as computed by CompiledNetwork::compute_batch.
+    pub fn extend_batch_inputs(&self, state: &S, batch_inputs: &mut Vec<Value>) {
+        let input_count = self.network.input_layer.height();
+        assert!(
+            self.input_providers.len() <= input_count,
+            "Input layer too short ({}) for all values",
+            input_count
+        );
+
+        // Inputs without a provider are zero, same as when computing the network directly.
+        let row_start = batch_inputs.len();
+        batch_inputs.extend(
+            self.input_providers
+                .iter()
+                .map(|input_provider| input_provider(state)),
+        );
+        batch_inputs.resize(row_start + input_count, 0.0);
+    }
 
-        self.network.input_layer.update(input_values);
+    /// The values of the inputs of the network for the state, without computing it.
+    pub fn input_values(&self, state: &S) -> Vec<Value> {
+        self.input_providers
+            .iter()
+            .map(|input_provider| input_provider(state))
+            .collect()
     }
 }
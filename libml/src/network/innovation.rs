@@ -0,0 +1,150 @@
+//! Innovation numbers identify the nodes and inputs of a network across structural mutations, as in NEAT,
+//! so that the topologies of networks evolved from the same one can be aligned.
+//! Zero means that a node or input doesn't have one yet, as with older saves.
+
+use std::collections::{BTreeMap, HashMap};
+
+use itertools::{EitherOrBoth, Itertools};
+
+use super::{Network, node::NodeInput};
+
+pub type Innovation = u64;
+
+/// Any node of a network, since the nodes of the input layer don't have innovation numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKey {
+    Input(usize),
+    Compute(Innovation),
+}
+
+/// A structural mutation which gets the same innovation numbers wherever it's made during a generation,
+/// so that networks which made the same mutation independently still line up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StructuralMutation {
+    /// An input from the source node to the target node.
+    InputCreation { source: NodeKey, target: Innovation },
+
+    /// A node inserted into an input, along with the input and output of the new node.
+    InputSplit { input: Innovation },
+}
+
+/// Hands out the innovation numbers of a generation, see StructuralMutation.
+#[derive(Debug, Clone)]
+pub struct InnovationTracker {
+    next_innovation: Innovation,
+    mutation_innovations: HashMap<StructuralMutation, Innovation>,
+}
+
+impl InnovationTracker {
+    /// Continues from the innovation numbers of the network the generation is based on.
+    pub fn new(network: &Network) -> Self {
+        Self {
+            next_innovation: network.next_innovation.max(1),
+            mutation_innovations: HashMap::new(),
+        }
+    }
+
+    /// The first of `count` consecutive innovation numbers for the mutation, the same ones as when it was last made.
+    pub fn mutation_innovations(&mut self, mutation: StructuralMutation, count: u64) -> Innovation {
+        *self
+            .mutation_innovations
+            .entry(mutation)
+            .or_insert_with(|| {
+                let innovation = self.next_innovation;
+                self.next_innovation += count;
+                innovation
+            })
+    }
+
+    /// The first of `count` consecutive innovation numbers which aren't shared with any other mutation.
+    pub fn unique_innovations(&mut self, count: u64) -> Innovation {
+        let innovation = self.next_innovation;
+        self.next_innovation += count;
+        innovation
+    }
+
+    /// Makes the network skip the innovation numbers handed out so far, so that they're never reused.
+    pub fn update_network(&self, network: &mut Network) {
+        network.next_innovation = network.next_innovation.max(self.next_innovation);
+    }
+}
+
+/// An input of either or both of two aligned networks, see `Network::align_inputs`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlignedInput<'a> {
+    pub innovation: Innovation,
+    pub first: Option<&'a NodeInput>,
+    pub second: Option<&'a NodeInput>,
+}
+
+impl Network {
+    /// Gives innovation numbers to the nodes and inputs which don't have one, e.g. after loading an older save.
+    pub fn assign_innovations(&mut self) {
+        let mut next_innovation = self.next_innovation.max(1);
+        let mut assign = |innovation: &mut Innovation| {
+            if *innovation == 0 {
+                *innovation = next_innovation;
+                next_innovation += 1;
+            }
+        };
+
+        for node in self
+            .compute_layers
+            .iter_mut()
+            .flat_map(|comp_layer| &mut comp_layer.nodes)
+        {
+            assign(&mut node.innovation);
+            node.inputs
+                .iter_mut()
+                .for_each(|input| assign(&mut input.innovation));
+        }
+
+        self.next_innovation = next_innovation;
+    }
+
+    /// The key of a node, the layer index including the input layer.
+    pub fn node_key(&self, layer_index: usize, node_index: usize) -> Option<NodeKey> {
+        match layer_index.checked_sub(1) {
+            None => (node_index < self.input_layer.height()).then_some(NodeKey::Input(node_index)),
+            Some(comp_layer_index) => self
+                .compute_layers
+                .get(comp_layer_index)?
+                .nodes
+                .get(node_index)
+                .map(|node| NodeKey::Compute(node.innovation)),
+        }
+    }
+
+    /// Pairs up the inputs of two networks by their innovation numbers, in the order of them.
+    /// Inputs which only one of the networks has are paired with None, and ones without an innovation number are left out.
+    pub fn align_inputs<'a>(&'a self, other: &'a Network) -> Vec<AlignedInput<'a>> {
+        self.inputs_by_innovation()
+            .into_iter()
+            .merge_join_by(other.inputs_by_innovation(), |(a, _), (b, _)| a.cmp(b))
+            .map(|inputs| {
+                let (first, second) = match inputs {
+                    EitherOrBoth::Both((_, first), (_, second)) => (Some(first), Some(second)),
+                    EitherOrBoth::Left((_, first)) => (Some(first), None),
+                    EitherOrBoth::Right((_, second)) => (None, Some(second)),
+                };
+
+                AlignedInput {
+                    // SAFETY: At least one of them is always there.
+                    innovation: first.or(second).unwrap().innovation,
+                    first,
+                    second,
+                }
+            })
+            .collect()
+    }
+
+    fn inputs_by_innovation(&self) -> BTreeMap<Innovation, &NodeInput> {
+        self.compute_layers
+            .iter()
+            .flat_map(|comp_layer| &comp_layer.nodes)
+            .flat_map(|node| &node.inputs)
+            .filter(|input| input.innovation != 0)
+            .map(|input| (input.innovation, input))
+            .collect()
+    }
+}
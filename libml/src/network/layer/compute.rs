@@ -1,30 +1,78 @@
 use serde::{Deserialize, Serialize};
 
-use crate::network::{node::Node, NetworkConfig, Value};
+use crate::network::{
+    NetworkConfig, Value,
+    functions::{Activator, Combinator},
+    node::Node,
+};
 
 use super::Layer;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ComputeLayer {
     pub nodes: Vec<Node>,
+
+    /// Overrides the activator of the network for this layer.
+    #[serde(default)]
+    pub activator: Option<Activator>,
+
+    /// Overrides the combinator of the network for this layer.
+    #[serde(default)]
+    pub combinator: Option<Combinator>,
 }
 
 impl ComputeLayer {
     pub fn default_n_nodes(count: usize) -> Self {
         let nodes = vec![Node::default(); count];
-        Self { nodes }
+
+        Self {
+            nodes,
+            activator: None,
+            combinator: None,
+        }
     }
-}
 
-impl Layer for ComputeLayer {
-    fn get_outputs(&self, config: &NetworkConfig, inputs: Option<Vec<Value>>) -> Vec<Value> {
-        let inputs = inputs.expect("Compute layer wasn't given inputs");
+    pub fn recurrent_node_count(&self) -> usize {
+        self.nodes.iter().filter(|node| node.is_recurrent()).count()
+    }
+
+    /// Computes the outputs of the layer like `get_outputs`, given the previous outputs of its recurrent nodes in order.
+    /// The recurrent values are replaced with the new outputs, to be given to the next computation.
+    pub fn compute_recurrent(
+        &self,
+        config: &NetworkConfig,
+        previous_layer_values: &[Vec<Value>],
+        recurrent_values: &mut [Value],
+    ) -> Vec<Value> {
+        let config = config.with_overrides(self.activator, self.combinator);
+        let mut recurrent_values = recurrent_values.iter_mut();
 
         self.nodes
             .iter()
-            .map(|node| node.compute(config, &inputs))
+            .map(|node| {
+                if !node.is_recurrent() {
+                    return node.compute(&config, previous_layer_values, 0.0);
+                }
+
+                let recurrent_value = recurrent_values.next().expect("Missing recurrent value");
+                let output = node.compute(&config, previous_layer_values, *recurrent_value);
+                *recurrent_value = output;
+                output
+            })
             .collect()
     }
+}
+
+impl Layer for ComputeLayer {
+    fn get_outputs(
+        &self,
+        config: &NetworkConfig,
+        previous_layer_values: &[Vec<Value>],
+    ) -> Vec<Value> {
+        // Recurrent nodes start from zero, as if the layer was computed for the first time.
+        let mut recurrent_values = vec![0.0; self.recurrent_node_count()];
+        self.compute_recurrent(config, previous_layer_values, &mut recurrent_values)
+    }
 
     fn output_node_indices(&self) -> Vec<usize> {
         (0..self.nodes.len())
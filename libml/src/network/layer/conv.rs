@@ -0,0 +1,350 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::network::{Value, functions::Activator};
+
+/// What the kernels of a convolutional layer see past the edges of a feature map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Padding {
+    /// Everything past the edges is zero.
+    #[default]
+    Zeros,
+
+    /// The map wraps around at the edges, as toroidal boards do.
+    Wrap,
+
+    /// The values on the edges repeat outwards.
+    Replicate,
+}
+
+impl Padding {
+    /// The coordinate read for a coordinate which might be past the edges of an axis of the given length,
+    /// or None if it reads as zero. The length has to be non-zero.
+    fn source_coordinate(self, coordinate: isize, length: usize) -> Option<usize> {
+        let length = length as isize;
+
+        match self {
+            Padding::Zeros => (0..length)
+                .contains(&coordinate)
+                .then_some(coordinate as usize),
+            Padding::Wrap => Some(coordinate.rem_euclid(length) as usize),
+            Padding::Replicate => Some(coordinate.clamp(0, length - 1) as usize),
+        }
+    }
+}
+
+/// Values on a grid with the same number of channels at every position,
+/// stored position by position and column by column (x outer, y inner) like kernels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureMap {
+    width: usize,
+    height: usize,
+    channels: usize,
+    values: Vec<Value>,
+}
+
+impl FeatureMap {
+    pub fn zeros(width: usize, height: usize, channels: usize) -> Self {
+        Self {
+            width,
+            height,
+            channels,
+            values: vec![0.0; width * height * channels],
+        }
+    }
+
+    pub fn from_values(width: usize, height: usize, channels: usize, values: Vec<Value>) -> Self {
+        assert_eq!(
+            values.len(),
+            width * height * channels,
+            "Wrong number of values for a {}x{} feature map with {} channels",
+            width,
+            height,
+            channels
+        );
+
+        Self {
+            width,
+            height,
+            channels,
+            values,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// The values of every channel at a position.
+    pub fn position_values(&self, x: usize, y: usize) -> &[Value] {
+        let first_value_index = self.first_value_index(x, y);
+        &self.values[first_value_index..first_value_index + self.channels]
+    }
+
+    pub fn position_values_mut(&mut self, x: usize, y: usize) -> &mut [Value] {
+        let first_value_index = self.first_value_index(x, y);
+        &mut self.values[first_value_index..first_value_index + self.channels]
+    }
+
+    fn first_value_index(&self, x: usize, y: usize) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "Position ({}, {}) out of bounds",
+            x,
+            y
+        );
+
+        (x * self.height + y) * self.channels
+    }
+
+    /// Makes the map the given size, reusing its allocation. The values are left unspecified.
+    pub(crate) fn reset(&mut self, width: usize, height: usize, channels: usize) {
+        self.width = width;
+        self.height = height;
+        self.channels = channels;
+        self.values.resize(width * height * channels, 0.0);
+    }
+}
+
+/// Slides the same kernel of weights over every position of a feature map, outputting a map of the same size.
+/// Each output channel is the activated weighted sum of every input channel within the kernel, plus a bias.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConvLayer {
+    kernel_diameter: usize,
+    input_channels: usize,
+
+    /// The weights of each output channel one after another, each going over the kernel column by column
+    /// and having a weight for every input channel at each offset.
+    weights: Vec<Value>,
+
+    /// One for each output channel.
+    biases: Vec<Value>,
+
+    pub padding: Padding,
+
+    /// Overrides the activator of the network for this layer.
+    pub activator: Option<Activator>,
+}
+
+impl ConvLayer {
+    /// Creates a layer with zero weights and biases, the kernel diameter has to be odd to have a center.
+    pub fn new(
+        kernel_diameter: usize,
+        input_channels: usize,
+        output_channels: usize,
+        padding: Padding,
+    ) -> Self {
+        assert!(
+            kernel_diameter % 2 == 1,
+            "Kernel diameter ({}) must be odd",
+            kernel_diameter
+        );
+
+        Self {
+            kernel_diameter,
+            input_channels,
+            weights: vec![0.0; output_channels * kernel_diameter.pow(2) * input_channels],
+            biases: vec![0.0; output_channels],
+            padding,
+            activator: None,
+        }
+    }
+
+    pub fn kernel_diameter(&self) -> usize {
+        self.kernel_diameter
+    }
+
+    pub fn input_channels(&self) -> usize {
+        self.input_channels
+    }
+
+    pub fn output_channels(&self) -> usize {
+        self.biases.len()
+    }
+
+    /// The weight from an input channel at a position of the kernel, (0, 0) being its top left corner.
+    pub fn weight(
+        &self,
+        output_channel: usize,
+        kernel_x: usize,
+        kernel_y: usize,
+        input_channel: usize,
+    ) -> Value {
+        self.weights[self.weight_index(output_channel, kernel_x, kernel_y, input_channel)]
+    }
+
+    pub fn set_weight(
+        &mut self,
+        output_channel: usize,
+        kernel_x: usize,
+        kernel_y: usize,
+        input_channel: usize,
+        weight: Value,
+    ) {
+        let weight_index = self.weight_index(output_channel, kernel_x, kernel_y, input_channel);
+        self.weights[weight_index] = weight;
+    }
+
+    pub fn bias(&self, output_channel: usize) -> Value {
+        self.biases[output_channel]
+    }
+
+    pub fn set_bias(&mut self, output_channel: usize, bias: Value) {
+        self.biases[output_channel] = bias;
+    }
+
+    pub fn weights_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.weights.iter_mut()
+    }
+
+    pub fn biases_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.biases.iter_mut()
+    }
+
+    /// Every weight of the layer followed by every bias.
+    pub fn parameters(&self) -> impl Iterator<Item = Value> {
+        self.weights.iter().chain(&self.biases).copied()
+    }
+
+    pub fn parameters_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.weights.iter_mut().chain(&mut self.biases)
+    }
+
+    /// The number of weights and biases the layer should have, which a deserialized layer might not.
+    pub(crate) fn expected_parameter_count(&self) -> usize {
+        (self.kernel_diameter.pow(2) * self.input_channels + 1) * self.output_channels()
+    }
+
+    /// Computes the output map, given the activator of the network for when the layer doesn't override it.
+    pub fn compute(&self, activator: Activator, inputs: &FeatureMap) -> FeatureMap {
+        let mut outputs = FeatureMap::zeros(0, 0, 0);
+        self.compute_into(activator, inputs, &mut outputs);
+        outputs
+    }
+
+    /// Computes the output map like `compute`, but writes it over the given map to reuse its allocation.
+    pub fn compute_into(
+        &self,
+        activator: Activator,
+        inputs: &FeatureMap,
+        outputs: &mut FeatureMap,
+    ) {
+        assert_eq!(
+            inputs.channels, self.input_channels,
+            "Wrong number of input channels"
+        );
+
+        let activator = self.activator.unwrap_or(activator);
+        let kernel_radius = (self.kernel_diameter / 2) as isize;
+        let kernel_weight_count = self.kernel_diameter.pow(2) * self.input_channels;
+
+        outputs.reset(inputs.width, inputs.height, self.output_channels());
+
+        for x in 0..inputs.width {
+            for y in 0..inputs.height {
+                let output_values = outputs.position_values_mut(x, y);
+                output_values.copy_from_slice(&self.biases);
+
+                // Each position of the kernel is looked up once and then weighted for every output channel.
+                for (kernel_x, kernel_y) in
+                    (0..self.kernel_diameter).cartesian_product(0..self.kernel_diameter)
+                {
+                    let source_x = self.padding.source_coordinate(
+                        x as isize + kernel_x as isize - kernel_radius,
+                        inputs.width,
+                    );
+                    let source_y = self.padding.source_coordinate(
+                        y as isize + kernel_y as isize - kernel_radius,
+                        inputs.height,
+                    );
+
+                    let (Some(source_x), Some(source_y)) = (source_x, source_y) else {
+                        continue;
+                    };
+
+                    let input_values = inputs.position_values(source_x, source_y);
+                    let first_weight_index =
+                        (kernel_x * self.kernel_diameter + kernel_y) * self.input_channels;
+
+                    for (output_channel, sum) in output_values.iter_mut().enumerate() {
+                        let weights = &self.weights
+                            [output_channel * kernel_weight_count + first_weight_index..]
+                            [..self.input_channels];
+
+                        *sum += input_values
+                            .iter()
+                            .zip(weights)
+                            .map(|(value, weight)| value * weight)
+                            .sum::<Value>();
+                    }
+                }
+
+                for value in output_values {
+                    *value = activator.activate(*value);
+                }
+            }
+        }
+    }
+
+    fn weight_index(
+        &self,
+        output_channel: usize,
+        kernel_x: usize,
+        kernel_y: usize,
+        input_channel: usize,
+    ) -> usize {
+        assert!(
+            output_channel < self.output_channels()
+                && kernel_x < self.kernel_diameter
+                && kernel_y < self.kernel_diameter
+                && input_channel < self.input_channels,
+            "Weight ({}, {}, {}, {}) out of bounds",
+            output_channel,
+            kernel_x,
+            kernel_y,
+            input_channel
+        );
+
+        ((output_channel * self.kernel_diameter + kernel_x) * self.kernel_diameter + kernel_y)
+            * self.input_channels
+            + input_channel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sums the 3x3 area around each position of a 3x1 map, where every padding mode reads different values.
+    fn sum_row(padding: Padding) -> Vec<Value> {
+        let mut layer = ConvLayer::new(3, 1, 1, padding);
+        for (kernel_x, kernel_y) in (0..3).cartesian_product(0..3) {
+            layer.set_weight(0, kernel_x, kernel_y, 0, 1.0);
+        }
+
+        let inputs = FeatureMap::from_values(3, 1, 1, vec![1.0, 2.0, 3.0]);
+        layer
+            .compute(Activator::Identity, &inputs)
+            .values()
+            .to_vec()
+    }
+
+    #[test]
+    fn padding_modes() {
+        assert_eq!(sum_row(Padding::Zeros), [3.0, 6.0, 5.0]);
+        assert_eq!(sum_row(Padding::Wrap), [18.0, 18.0, 18.0]);
+        assert_eq!(sum_row(Padding::Replicate), [12.0, 18.0, 24.0]);
+    }
+}
@@ -0,0 +1,356 @@
+use std::fmt;
+
+use crate::network::{
+    NetworkConfig, Value,
+    functions::{Activator, Combinator},
+    innovation::Innovation,
+    node::{self, Node, NodeInput},
+};
+
+use super::{Layer, compute::ComputeLayer};
+
+/// A compute layer stored as a weight matrix over the previous layer, which suits densely connected layers better.
+/// Converts losslessly from and to a ComputeLayer, apart from the inputs of each node ending up ordered by their source.
+/// Layers with skip connections or recurrent nodes can't be dense, as the rows only cover the previous layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenseLayer {
+    /// The height of the previous layer, which is the length of each row.
+    input_count: usize,
+
+    /// One row per node.
+    weights: Vec<Value>,
+
+    /// Whether each weight is an actual input, so that missing inputs can be told apart from zero weights.
+    mask: Vec<bool>,
+
+    /// The innovation numbers of the inputs, laid out like the weights.
+    input_innovations: Vec<Innovation>,
+
+    biases: Vec<Value>,
+    node_activators: Vec<Option<Activator>>,
+    node_combinators: Vec<Option<Combinator>>,
+    node_innovations: Vec<Innovation>,
+
+    /// Overrides the activator of the network for this layer.
+    pub activator: Option<Activator>,
+
+    /// Overrides the combinator of the network for this layer.
+    pub combinator: Option<Combinator>,
+}
+
+/// Why a compute layer can't be represented as a dense layer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DenseConversionError {
+    InputOutOfBounds {
+        node_index: usize,
+        source_node_index: usize,
+        input_count: usize,
+    },
+    DuplicateInput {
+        node_index: usize,
+        source_node_index: usize,
+    },
+    SkipConnection {
+        node_index: usize,
+        source_node_index: usize,
+        skipped_layers: usize,
+    },
+    RecurrentNode {
+        node_index: usize,
+    },
+}
+
+impl fmt::Display for DenseConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DenseConversionError::InputOutOfBounds {
+                node_index,
+                source_node_index,
+                input_count,
+            } => write!(
+                f,
+                "Node {} has an input from node {}, but the previous layer only has {} nodes",
+                node_index, source_node_index, input_count
+            ),
+            DenseConversionError::DuplicateInput {
+                node_index,
+                source_node_index,
+            } => write!(
+                f,
+                "Node {} has more than one input from node {}",
+                node_index, source_node_index
+            ),
+            DenseConversionError::SkipConnection {
+                node_index,
+                source_node_index,
+                skipped_layers,
+            } => write!(
+                f,
+                "Node {} has an input from node {} which skips {} layers",
+                node_index, source_node_index, skipped_layers
+            ),
+            DenseConversionError::RecurrentNode { node_index } => {
+                write!(f, "Node {} is recurrent", node_index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DenseConversionError {}
+
+impl DenseLayer {
+    pub fn from_sparse(
+        layer: &ComputeLayer,
+        input_count: usize,
+    ) -> Result<Self, DenseConversionError> {
+        let height = layer.nodes.len();
+
+        let mut dense_layer = Self {
+            input_count,
+            weights: vec![0.0; height * input_count],
+            mask: vec![false; height * input_count],
+            input_innovations: vec![0; height * input_count],
+            biases: Vec::with_capacity(height),
+            node_activators: Vec::with_capacity(height),
+            node_combinators: Vec::with_capacity(height),
+            node_innovations: Vec::with_capacity(height),
+            activator: layer.activator,
+            combinator: layer.combinator,
+        };
+
+        for (node_index, node) in layer.nodes.iter().enumerate() {
+            if node.is_recurrent() {
+                return Err(DenseConversionError::RecurrentNode { node_index });
+            }
+
+            for input in &node.inputs {
+                if input.skipped_layers > 0 {
+                    return Err(DenseConversionError::SkipConnection {
+                        node_index,
+                        source_node_index: input.node_index,
+                        skipped_layers: input.skipped_layers,
+                    });
+                }
+
+                if input.node_index >= input_count {
+                    return Err(DenseConversionError::InputOutOfBounds {
+                        node_index,
+                        source_node_index: input.node_index,
+                        input_count,
+                    });
+                }
+
+                let weight_index = node_index * input_count + input.node_index;
+                if dense_layer.mask[weight_index] {
+                    return Err(DenseConversionError::DuplicateInput {
+                        node_index,
+                        source_node_index: input.node_index,
+                    });
+                }
+
+                dense_layer.weights[weight_index] = input.weight;
+                dense_layer.mask[weight_index] = true;
+                dense_layer.input_innovations[weight_index] = input.innovation;
+            }
+
+            dense_layer.biases.push(node.bias);
+            dense_layer.node_activators.push(node.activator);
+            dense_layer.node_combinators.push(node.combinator);
+            dense_layer.node_innovations.push(node.innovation);
+        }
+
+        Ok(dense_layer)
+    }
+
+    pub fn to_sparse(&self) -> ComputeLayer {
+        let nodes = (0..self.height())
+            .map(|node_index| Node {
+                inputs: self.node_inputs(node_index).collect(),
+                bias: self.biases[node_index],
+                activator: self.node_activators[node_index],
+                combinator: self.node_combinators[node_index],
+                innovation: self.node_innovations[node_index],
+                recurrent_weight: None,
+            })
+            .collect();
+
+        ComputeLayer {
+            nodes,
+            activator: self.activator,
+            combinator: self.combinator,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.biases.len()
+    }
+
+    pub fn input_count(&self) -> usize {
+        self.input_count
+    }
+
+    /// The weight of the input of a node from a node of the previous layer, if it has one.
+    pub fn weight(&self, node_index: usize, source_node_index: usize) -> Option<Value> {
+        let weight_index = self.weight_index(node_index, source_node_index);
+        self.mask[weight_index].then(|| self.weights[weight_index])
+    }
+
+    /// Sets the weight of the input of a node from a node of the previous layer, None removing the input.
+    /// Inputs added this way don't have an innovation number yet.
+    pub fn set_weight(
+        &mut self,
+        node_index: usize,
+        source_node_index: usize,
+        weight: Option<Value>,
+    ) {
+        let weight_index = self.weight_index(node_index, source_node_index);
+        if !self.mask[weight_index] || weight.is_none() {
+            self.input_innovations[weight_index] = 0;
+        }
+
+        self.weights[weight_index] = weight.unwrap_or(0.0);
+        self.mask[weight_index] = weight.is_some();
+    }
+
+    /// The weights and mask of every node one row after another, see `input_count` for the row length.
+    pub(crate) fn matrix(&self) -> (&[Value], &[bool]) {
+        (&self.weights, &self.mask)
+    }
+
+    fn weight_index(&self, node_index: usize, source_node_index: usize) -> usize {
+        assert!(
+            node_index < self.height() && source_node_index < self.input_count,
+            "Weight ({}, {}) out of bounds",
+            node_index,
+            source_node_index
+        );
+
+        node_index * self.input_count + source_node_index
+    }
+
+    fn node_inputs(&self, node_index: usize) -> impl Iterator<Item = NodeInput> {
+        let row = node_index * self.input_count..(node_index + 1) * self.input_count;
+
+        self.weights[row.clone()]
+            .iter()
+            .zip(&self.mask[row.clone()])
+            .zip(&self.input_innovations[row])
+            .enumerate()
+            .filter(|(_, ((_, is_input), _))| **is_input)
+            .map(|(source_node_index, ((weight, _), innovation))| NodeInput {
+                innovation: *innovation,
+                ..NodeInput::new(source_node_index, *weight)
+            })
+    }
+}
+
+impl ComputeLayer {
+    /// Converts the layer into a dense one, given the height of the previous layer.
+    pub fn to_dense(&self, input_count: usize) -> Result<DenseLayer, DenseConversionError> {
+        DenseLayer::from_sparse(self, input_count)
+    }
+}
+
+impl Layer for DenseLayer {
+    fn get_outputs(
+        &self,
+        config: &NetworkConfig,
+        previous_layer_values: &[Vec<Value>],
+    ) -> Vec<Value> {
+        let inputs = previous_layer_values.last().expect("Missing input layer");
+        assert!(inputs.len() >= self.input_count, "Missing input");
+        let config = config.with_overrides(self.activator, self.combinator);
+
+        (0..self.height())
+            .map(|node_index| {
+                let node_config = config.with_overrides(
+                    self.node_activators[node_index],
+                    self.node_combinators[node_index],
+                );
+
+                let weighted_values = self
+                    .node_inputs(node_index)
+                    .map(|input| inputs[input.node_index] * input.weight);
+
+                node::compute_weighted(&node_config, weighted_values, self.biases[node_index])
+            })
+            .collect()
+    }
+
+    fn output_node_indices(&self) -> Vec<usize> {
+        (0..self.height()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(node_index: usize, weight: Value, innovation: Innovation) -> NodeInput {
+        NodeInput {
+            innovation,
+            ..NodeInput::new(node_index, weight)
+        }
+    }
+
+    #[test]
+    fn sparse_layer_round_trips() {
+        // The inputs are ordered by their source, as to_sparse orders them.
+        let layer = ComputeLayer {
+            nodes: vec![
+                Node {
+                    // A zero weight is still an input, unlike the missing inputs from nodes 1 and 3.
+                    inputs: vec![input(0, 0.0, 4), input(2, -1.5, 5)],
+                    bias: 0.25,
+                    activator: Some(Activator::Sigmoid),
+                    innovation: 1,
+                    ..Node::default()
+                },
+                Node {
+                    inputs: vec![],
+                    combinator: Some(Combinator::Mul),
+                    innovation: 2,
+                    ..Node::default()
+                },
+                Node {
+                    inputs: vec![input(1, 2.0, 6), input(2, 0.0, 0), input(3, 0.5, 7)],
+                    bias: -1.0,
+                    innovation: 3,
+                    ..Node::default()
+                },
+            ],
+            activator: Some(Activator::Tanh),
+            combinator: Some(Combinator::Mean),
+        };
+
+        let dense_layer = layer.to_dense(4).unwrap();
+        assert_eq!(dense_layer.weight(0, 0), Some(0.0));
+        assert_eq!(dense_layer.weight(0, 1), None);
+
+        assert_eq!(dense_layer.to_sparse(), layer);
+    }
+
+    #[test]
+    fn skip_connections_cant_be_dense() {
+        let layer = ComputeLayer {
+            nodes: vec![Node {
+                inputs: vec![NodeInput {
+                    skipped_layers: 1,
+                    ..NodeInput::new(0, 1.0)
+                }],
+                ..Node::default()
+            }],
+            activator: None,
+            combinator: None,
+        };
+
+        assert_eq!(
+            layer.to_dense(1),
+            Err(DenseConversionError::SkipConnection {
+                node_index: 0,
+                source_node_index: 0,
+                skipped_layers: 1,
+            })
+        );
+    }
+}
@@ -4,50 +4,43 @@ use crate::network::{NetworkConfig, Value};
 
 use super::Layer;
 
+/// Passes the inputs given to the network on to the first compute layer.
+/// The values aren't stored, so that a network doesn't change while being computed.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InputLayer {
     height: usize,
-
-    // FIXME: It doesn't make sense that we're storing the values inside the network.
-    //        This is the only reason adapters need a mutable reference to the network.
-    //        We would still need the keys to be stable between serialization cycles, but not the last used values.
-    output_values: Vec<Value>,
 }
 
 impl InputLayer {
     pub fn new(height: usize) -> Self {
-        let output_values = vec![0.0; height];
-
-        Self {
-            height,
-            output_values,
-        }
+        Self { height }
     }
 
-    pub fn update<'a, I>(&mut self, values: I)
-    where
-        I: IntoIterator<Item = Value>,
-    {
-        let mut value_iter = values.into_iter();
-        let mut target_value_iter = self.output_values.iter_mut();
-
-        while let Some(value) = value_iter.next() {
-            let Some(target_value) = target_value_iter.next() else {
-                panic!("Input layer too short ({}) for all values", self.output_values.len());
-            };
-
-            *target_value = value;
-        }
+    pub fn height(&self) -> usize {
+        self.height
     }
 }
 
 impl Layer for InputLayer {
-    fn get_outputs(&self, _config: &NetworkConfig, _inputs: Option<Vec<Value>>) -> Vec<Value> {
-        self.output_values.clone()
+    fn get_outputs(
+        &self,
+        _config: &NetworkConfig,
+        previous_layer_values: &[Vec<Value>],
+    ) -> Vec<Value> {
+        let mut inputs = previous_layer_values.last().cloned().unwrap_or_default();
+
+        assert!(
+            inputs.len() <= self.height,
+            "Input layer too short ({}) for all values",
+            self.height
+        );
+
+        inputs.resize(self.height, 0.0);
+        inputs
     }
 
     fn output_node_indices(&self) -> Vec<usize> {
-        (0..self.output_values.len())
+        (0..self.height)
             .collect()
     }
 }
@@ -1,10 +1,18 @@
 use super::{NetworkConfig, Value};
 
 pub mod compute;
+pub mod conv;
+pub mod dense;
 pub mod input;
 
 pub trait Layer {
-    fn get_outputs(&self, config: &NetworkConfig, inputs: Option<Vec<Value>>) -> Vec<Value>;
+    /// Computes the outputs of the layer from the outputs of every layer before it, the last one being the previous layer.
+    /// The input layer is given the inputs of the network as its only previous layer.
+    fn get_outputs(
+        &self,
+        config: &NetworkConfig,
+        previous_layer_values: &[Vec<Value>],
+    ) -> Vec<Value>;
 
     // A vtable can't be built with an "impl Iterator" return type :(
     fn output_node_indices(&self) -> Vec<usize>;
@@ -1,14 +1,22 @@
-use std::iter;
+use std::{borrow::Cow, iter, mem};
 
 use functions::{Activator, Combinator};
+use innovation::Innovation;
 use itertools::Itertools;
 use layer::{Layer, compute::ComputeLayer, input::InputLayer};
 use serde::{Deserialize, Serialize};
 
+pub mod compiled;
+pub mod conv;
+pub mod gradient;
 pub mod harness;
+pub mod innovation;
 pub mod layer;
 pub mod node;
 pub mod functions;
+pub mod optimizer;
+pub mod structure;
+pub mod validation;
 
 pub type Value = f32;
 
@@ -16,6 +24,23 @@ pub type Value = f32;
 pub struct NetworkConfig {
     pub activator: Activator,
     pub combinator: Combinator,
+    pub activation_placement: ActivationPlacement,
+}
+
+impl NetworkConfig {
+    /// The config with the functions replaced by the given ones, if any.
+    /// Used to resolve the functions of layers and nodes, which may override the ones of the network.
+    pub fn with_overrides(
+        &self,
+        activator: Option<Activator>,
+        combinator: Option<Combinator>,
+    ) -> Self {
+        Self {
+            activator: activator.unwrap_or(self.activator),
+            combinator: combinator.unwrap_or(self.combinator),
+            ..*self
+        }
+    }
 }
 
 impl Default for NetworkConfig {
@@ -23,16 +48,31 @@ impl Default for NetworkConfig {
         Self {
             activator: Activator::Tanh,
             combinator: Combinator::Add,
+            activation_placement: ActivationPlacement::ActivateSums,
         }
     }
 }
 
+/// Where the activator is applied in a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ActivationPlacement {
+    /// Each weighted input is activated before combining, i.e. `sum(act(w * x)) + bias`.
+    /// This is how networks used to work, so older saves are assumed to be in this mode.
+    ActivateInputs,
+
+    /// The weighted inputs are combined before activating, i.e. `act(sum(w * x) + bias)` as is usual.
+    ActivateSums,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Network {
-    #[serde(skip)]
     pub config: NetworkConfig,
     pub input_layer: InputLayer,
     pub compute_layers: Vec<ComputeLayer>,
+
+    /// The first innovation number which isn't used in the network yet, see the innovation module.
+    #[serde(default)]
+    pub next_innovation: Innovation,
 }
 
 impl Network {
@@ -55,17 +95,68 @@ impl Network {
             hidden_layers.chain(output_layer_iter).collect_vec()
         };
 
-        Self {
+        let mut network = Self {
             config,
             input_layer,
             compute_layers,
+            next_innovation: 0,
+        };
+
+        network.assign_innovations();
+        network
+    }
+
+    /// Computes the outputs of the network for the given inputs, missing inputs being zero.
+    /// Recurrent nodes start from zero every time, see `compute_recurrent` for keeping their values.
+    pub fn compute(&self, inputs: &[Value]) -> Vec<Value> {
+        let mut recurrent_values = vec![0.0; self.recurrent_node_count()];
+        self.compute_recurrent(inputs, &mut recurrent_values)
+    }
+
+    /// Computes the network like `compute`, given the previous outputs of its recurrent nodes in the order of the nodes.
+    /// The recurrent values are replaced with the new outputs, so that the next computation can remember this one.
+    pub fn compute_recurrent(&self, inputs: &[Value], recurrent_values: &mut [Value]) -> Vec<Value> {
+        // SAFETY: There's always at least the input layer.
+        self.compute_layer_values(inputs, recurrent_values)
+            .pop()
+            .unwrap()
+    }
+
+    /// The outputs of every layer for the given inputs, starting from the input layer.
+    /// All of them are kept since skip connections can reach back to any earlier layer.
+    fn compute_layer_values(
+        &self,
+        inputs: &[Value],
+        recurrent_values: &mut [Value],
+    ) -> Vec<Vec<Value>> {
+        assert_eq!(
+            recurrent_values.len(),
+            self.recurrent_node_count(),
+            "Recurrent values don't match the network"
+        );
+
+        let mut layer_values = vec![self.input_layer.get_outputs(&self.config, &[inputs.to_vec()])];
+        let mut recurrent_values = recurrent_values;
+
+        for comp_layer in &self.compute_layers {
+            let (layer_recurrent_values, rest) = mem::take(&mut recurrent_values)
+                .split_at_mut(comp_layer.recurrent_node_count());
+            recurrent_values = rest;
+
+            let values =
+                comp_layer.compute_recurrent(&self.config, &layer_values, layer_recurrent_values);
+            layer_values.push(values);
         }
+
+        layer_values
     }
 
-    pub fn compute(&self) -> Vec<Value> {
-        self.layers()
-            .fold(None, |inputs, layer| Some(layer.get_outputs(&self.config, inputs)))
-            .expect("No layers")
+    /// The number of recurrent nodes, which is how many values the network needs to remember between computations.
+    pub fn recurrent_node_count(&self) -> usize {
+        self.compute_layers
+            .iter()
+            .map(|comp_layer| comp_layer.recurrent_node_count())
+            .sum()
     }
 
     pub fn layers(&self) -> impl Iterator<Item = &dyn Layer> {
@@ -80,3 +171,15 @@ impl Network {
         self.layers().nth(index)
     }
 }
+
+impl<'a> From<&'a Network> for Cow<'a, Network> {
+    fn from(network: &'a Network) -> Self {
+        Cow::Borrowed(network)
+    }
+}
+
+impl From<Network> for Cow<'_, Network> {
+    fn from(network: Network) -> Self {
+        Cow::Owned(network)
+    }
+}
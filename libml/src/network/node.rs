@@ -1,21 +1,87 @@
 use serde::{Deserialize, Serialize};
 
 use super::{
-    NetworkConfig, Value
+    ActivationPlacement, NetworkConfig, Value,
+    functions::{Activator, Combinator},
+    innovation::Innovation,
 };
 
+// NOTE: Networks are saved as MessagePack arrays, so new fields must be added last and have defaults
+//       for older saves to keep loading.
 #[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
 pub struct Node {
     pub inputs: Vec<NodeInput>,
+
+    /// Added to the combined inputs, so that a node isn't limited to what its inputs can produce.
+    #[serde(default)]
+    pub bias: Value,
+
+    /// Overrides the activator of the layer for this node.
+    #[serde(default)]
+    pub activator: Option<Activator>,
+
+    /// Overrides the combinator of the layer for this node.
+    #[serde(default)]
+    pub combinator: Option<Combinator>,
+
+    /// Identifies the node across structural mutations, see the innovation module.
+    #[serde(default)]
+    pub innovation: Innovation,
+
+    /// Makes the node recurrent, feeding its output from the previous computation back into it
+    /// as an extra input with this weight, after the other ones. The output starts out as zero.
+    #[serde(default)]
+    pub recurrent_weight: Option<Value>,
 }
 
 impl Node {
-    pub fn compute(&self, config: &NetworkConfig, input_values: &Vec<Value>) -> Value {
-        self.inputs
+    /// Computes the output of the node, the given config being the one of the layer the node is in.
+    /// The values are the outputs of every layer before the node's, the last one being the previous layer.
+    /// The recurrent value is the previous output of the node, which is ignored if it isn't recurrent.
+    pub fn compute(
+        &self,
+        config: &NetworkConfig,
+        previous_layer_values: &[Vec<Value>],
+        recurrent_value: Value,
+    ) -> Value {
+        let config = config.with_overrides(self.activator, self.combinator);
+
+        let weighted_values = self
+            .inputs
             .iter()
-            .map(|input| input.compute(config, input_values))
-            .reduce(|a, b| config.combinator.combine(a, b))
-            .unwrap_or(0.0)
+            .map(|input| input.weighted_value(previous_layer_values))
+            .chain(
+                self.recurrent_weight
+                    .map(|recurrent_weight| recurrent_value * recurrent_weight),
+            );
+
+        compute_weighted(&config, weighted_values, self.bias)
+    }
+
+    pub fn is_recurrent(&self) -> bool {
+        self.recurrent_weight.is_some()
+    }
+}
+
+/// Computes the output of a node from its weighted input values, the config being the resolved one of the node.
+pub(crate) fn compute_weighted<I>(config: &NetworkConfig, weighted_values: I, bias: Value) -> Value
+where
+    I: Iterator<Item = Value>,
+{
+    match config.activation_placement {
+        ActivationPlacement::ActivateInputs => {
+            let combined_inputs = config
+                .combinator
+                .combine_all(weighted_values.map(|value| config.activator.activate(value)))
+                .unwrap_or(0.0);
+
+            combined_inputs + bias
+        }
+        ActivationPlacement::ActivateSums => {
+            let combined_inputs = config.combinator.combine_all(weighted_values).unwrap_or(0.0);
+
+            config.activator.activate(combined_inputs + bias)
+        }
     }
 }
 
@@ -23,12 +89,46 @@ impl Node {
 pub struct NodeInput {
     pub node_index: usize,
     pub weight: Value,
+
+    /// How many layers are skipped to get to the layer of the source node,
+    /// zero meaning that it's in the previous layer as in older saves.
+    #[serde(default)]
+    pub skipped_layers: usize,
+
+    /// Identifies the connection across structural mutations, see the innovation module.
+    #[serde(default)]
+    pub innovation: Innovation,
 }
 
 impl NodeInput {
-    fn compute(&self, config: &NetworkConfig, input_values: &Vec<Value>) -> Value {
-        let input_value = input_values.get(self.node_index).expect("Missing input");
-        let weighted_value = input_value * self.weight;
-        config.activator.activate(weighted_value)
+    /// An input from a node of the previous layer.
+    pub fn new(node_index: usize, weight: Value) -> Self {
+        Self {
+            node_index,
+            weight,
+            skipped_layers: 0,
+            innovation: 0,
+        }
+    }
+
+    /// The index of the layer of the source node, given the layer index of the node the input belongs to.
+    /// None if the input skips past the input layer.
+    pub fn source_layer_index(&self, layer_index: usize) -> Option<usize> {
+        layer_index.checked_sub(self.skipped_layers + 1)
+    }
+
+    /// The value of the source node, given the outputs of every layer before the node's.
+    pub fn source_value(&self, previous_layer_values: &[Vec<Value>]) -> Value {
+        let source_layer_index = self
+            .source_layer_index(previous_layer_values.len())
+            .expect("Missing input layer");
+
+        *previous_layer_values[source_layer_index]
+            .get(self.node_index)
+            .expect("Missing input")
+    }
+
+    fn weighted_value(&self, previous_layer_values: &[Vec<Value>]) -> Value {
+        self.source_value(previous_layer_values) * self.weight
     }
 }
@@ -0,0 +1,117 @@
+use super::{Network, Value, gradient::Gradients};
+
+/// Updates the parameters of a network from the gradients of a loss, see `Network::backpropagate`.
+/// Optimizers keep state between steps for each parameter, which is reset if the number of parameters changes
+/// since the state can't be matched to the parameters anymore after structural changes.
+pub trait Optimizer {
+    fn step(&mut self, network: &mut Network, gradients: &Gradients);
+}
+
+/// Stochastic gradient descent with momentum.
+#[derive(Debug, Clone)]
+pub struct Sgd {
+    pub learning_rate: Value,
+
+    /// How much of the previous update carries over to the next one, zero for plain gradient descent.
+    pub momentum: Value,
+
+    velocities: Vec<Value>,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: Value, momentum: Value) -> Self {
+        Self {
+            learning_rate,
+            momentum,
+            velocities: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, network: &mut Network, gradients: &Gradients) {
+        reset_if_mismatched(&mut self.velocities, gradients);
+
+        for ((parameter, velocity), gradient) in network
+            .parameters_mut()
+            .zip(&mut self.velocities)
+            .zip(&gradients.0)
+        {
+            *velocity = self.momentum * *velocity - self.learning_rate * gradient;
+            *parameter += *velocity;
+        }
+    }
+}
+
+/// The Adam optimizer, which scales the learning rate of each parameter by how large and noisy its gradients are.
+#[derive(Debug, Clone)]
+pub struct Adam {
+    pub learning_rate: Value,
+
+    /// The decay rates of the moving averages of the gradients and of the squared gradients.
+    pub beta1: Value,
+    pub beta2: Value,
+
+    /// Avoids dividing by zero for parameters which haven't had gradients yet.
+    pub epsilon: Value,
+
+    first_moments: Vec<Value>,
+    second_moments: Vec<Value>,
+    step_count: i32,
+}
+
+impl Adam {
+    pub fn new(learning_rate: Value) -> Self {
+        Self {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            first_moments: Vec::new(),
+            second_moments: Vec::new(),
+            step_count: 0,
+        }
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, network: &mut Network, gradients: &Gradients) {
+        if reset_if_mismatched(&mut self.first_moments, gradients) {
+            reset_if_mismatched(&mut self.second_moments, gradients);
+            self.step_count = 0;
+        }
+
+        self.step_count += 1;
+
+        // The moments start from zero, which biases them towards it for the first steps.
+        let first_bias_correction = 1.0 - self.beta1.powi(self.step_count);
+        let second_bias_correction = 1.0 - self.beta2.powi(self.step_count);
+
+        for (((parameter, first_moment), second_moment), gradient) in network
+            .parameters_mut()
+            .zip(&mut self.first_moments)
+            .zip(&mut self.second_moments)
+            .zip(&gradients.0)
+        {
+            *first_moment = self.beta1 * *first_moment + (1.0 - self.beta1) * gradient;
+            *second_moment = self.beta2 * *second_moment + (1.0 - self.beta2) * gradient * gradient;
+
+            let corrected_first_moment = *first_moment / first_bias_correction;
+            let corrected_second_moment = *second_moment / second_bias_correction;
+
+            *parameter -= self.learning_rate * corrected_first_moment
+                / (corrected_second_moment.sqrt() + self.epsilon);
+        }
+    }
+}
+
+/// Resets the state to zeros if it doesn't have a value for every parameter, returning whether it was reset.
+fn reset_if_mismatched(state: &mut Vec<Value>, gradients: &Gradients) -> bool {
+    if state.len() == gradients.0.len() {
+        return false;
+    }
+
+    state.clear();
+    state.resize(gradients.0.len(), 0.0);
+    true
+}
@@ -0,0 +1,165 @@
+use super::{
+    Network,
+    functions::Activator,
+    innovation::Innovation,
+    layer::compute::ComputeLayer,
+    node::{Node, NodeInput},
+};
+
+/// Structural changes, which keep the inputs of every node pointing at the same source nodes as before.
+/// Compute layer indices don't include the input layer, and the output layer always stays the last one.
+impl Network {
+    /// Inserts an empty hidden layer before the compute layer at the given index.
+    /// The inputs of that layer skip the new one, so it can't be compiled densely until they're rewired.
+    pub fn insert_layer(&mut self, comp_layer_index: usize) {
+        assert!(
+            comp_layer_index < self.compute_layers.len(),
+            "Can't insert a layer after the output layer"
+        );
+
+        // The new layer takes the layer index of the compute layer it's inserted before.
+        let new_layer_index = comp_layer_index + 1;
+
+        self.for_each_later_input(comp_layer_index, |layer_index, input| {
+            if input
+                .source_layer_index(layer_index)
+                .is_some_and(|source_layer_index| source_layer_index < new_layer_index)
+            {
+                input.skipped_layers += 1;
+            }
+
+            true
+        });
+
+        self.compute_layers
+            .insert(comp_layer_index, ComputeLayer::default_n_nodes(0));
+    }
+
+    /// Removes a hidden layer, along with every input from its nodes.
+    pub fn remove_layer(&mut self, comp_layer_index: usize) {
+        assert!(
+            comp_layer_index + 1 < self.compute_layers.len(),
+            "Can't remove the output layer"
+        );
+
+        let removed_layer_index = comp_layer_index + 1;
+
+        self.for_each_later_input(comp_layer_index + 1, |layer_index, input| {
+            match input.source_layer_index(layer_index) {
+                Some(source_layer_index) if source_layer_index == removed_layer_index => false,
+                Some(source_layer_index) if source_layer_index < removed_layer_index => {
+                    input.skipped_layers -= 1;
+                    true
+                }
+                _ => true,
+            }
+        });
+
+        self.compute_layers.remove(comp_layer_index);
+    }
+
+    /// Removes a node of a hidden layer, along with every input from it.
+    pub fn remove_node(&mut self, comp_layer_index: usize, node_index: usize) {
+        assert!(
+            comp_layer_index + 1 < self.compute_layers.len(),
+            "Can't remove nodes of the output layer"
+        );
+
+        let removed_layer_index = comp_layer_index + 1;
+
+        self.for_each_later_input(comp_layer_index + 1, |layer_index, input| {
+            if input.source_layer_index(layer_index) != Some(removed_layer_index) {
+                return true;
+            }
+
+            if input.node_index == node_index {
+                return false;
+            }
+
+            if input.node_index > node_index {
+                input.node_index -= 1;
+            }
+
+            true
+        });
+
+        self.compute_layers[comp_layer_index]
+            .nodes
+            .remove(node_index);
+    }
+
+    /// Inserts a node into an input of a node, as NEAT does. The new node is put in the layer before the node,
+    /// which is inserted first if the input comes from there. Returns the compute layer and node indices of the new node.
+    ///
+    /// The new node passes its input on as is, so the outputs of the network don't change.
+    /// It gets the given innovation number, with its input and output getting the next two.
+    ///
+    /// NOTE: Inserting a layer turns the other inputs of the node's layer into skip connections past the new layer,
+    ///       so compiled networks compute that layer sparsely rather than as a dense matrix from then on.
+    pub fn split_input(
+        &mut self,
+        comp_layer_index: usize,
+        node_index: usize,
+        input_index: usize,
+        innovation: Innovation,
+    ) -> (usize, usize) {
+        let mut comp_layer_index = comp_layer_index;
+
+        if self.compute_layers[comp_layer_index].nodes[node_index].inputs[input_index]
+            .skipped_layers
+            == 0
+        {
+            self.insert_layer(comp_layer_index);
+            comp_layer_index += 1;
+        }
+
+        // Reading the input after inserting the layer, since that changes how many layers it skips.
+        let input = self.compute_layers[comp_layer_index].nodes[node_index].inputs[input_index];
+
+        let new_node = Node {
+            inputs: vec![NodeInput {
+                node_index: input.node_index,
+                weight: 1.0,
+                skipped_layers: input.skipped_layers - 1,
+                innovation: innovation + 1,
+            }],
+            activator: Some(Activator::Identity),
+            innovation,
+            ..Node::default()
+        };
+
+        let new_node_layer = &mut self.compute_layers[comp_layer_index - 1];
+        new_node_layer.nodes.push(new_node);
+        let new_node_index = new_node_layer.nodes.len() - 1;
+
+        self.compute_layers[comp_layer_index].nodes[node_index].inputs[input_index] = NodeInput {
+            node_index: new_node_index,
+            weight: input.weight,
+            skipped_layers: 0,
+            innovation: innovation + 2,
+        };
+
+        self.next_innovation = self.next_innovation.max(innovation + 3);
+
+        (comp_layer_index - 1, new_node_index)
+    }
+
+    /// Calls the function with every input of the nodes from the compute layer at the given index onwards,
+    /// along with the layer index of the node, keeping only the inputs for which it returns true.
+    fn for_each_later_input<F>(&mut self, first_comp_layer_index: usize, mut f: F)
+    where
+        F: FnMut(usize, &mut NodeInput) -> bool,
+    {
+        for (comp_layer_index, comp_layer) in self
+            .compute_layers
+            .iter_mut()
+            .enumerate()
+            .skip(first_comp_layer_index)
+        {
+            for node in &mut comp_layer.nodes {
+                node.inputs
+                    .retain_mut(|input| f(comp_layer_index + 1, input));
+            }
+        }
+    }
+}
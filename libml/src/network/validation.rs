@@ -0,0 +1,260 @@
+use std::fmt;
+
+use super::{Network, Value};
+
+/// Something that would make a network panic or misbehave during inference.
+/// Layer indices include the input layer, so the first compute layer has the index 1.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    NoComputeLayers,
+    InputOutOfBounds {
+        layer_index: usize,
+        node_index: usize,
+        source_layer_index: usize,
+        source_node_index: usize,
+        source_layer_height: usize,
+    },
+    SkipsPastInputLayer {
+        layer_index: usize,
+        node_index: usize,
+        source_node_index: usize,
+        skipped_layers: usize,
+    },
+    NonFiniteWeight {
+        layer_index: usize,
+        node_index: usize,
+        source_node_index: usize,
+        weight: Value,
+    },
+    NonFiniteBias {
+        layer_index: usize,
+        node_index: usize,
+        bias: Value,
+    },
+    NonFiniteRecurrentWeight {
+        layer_index: usize,
+        node_index: usize,
+        weight: Value,
+    },
+    InputCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    TooFewOutputs {
+        expected: usize,
+        actual: usize,
+    },
+    EvenKernelDiameter {
+        layer_index: usize,
+        kernel_diameter: usize,
+    },
+    ChannelCountMismatch {
+        layer_index: usize,
+        expected: usize,
+        actual: usize,
+    },
+    ParameterCountMismatch {
+        layer_index: usize,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::NoComputeLayers => write!(f, "The network has no compute layers"),
+            ValidationError::InputOutOfBounds {
+                layer_index,
+                node_index,
+                source_layer_index,
+                source_node_index,
+                source_layer_height,
+            } => write!(
+                f,
+                "Node {} of layer {} has an input from node {} of layer {}, which only has {} nodes",
+                node_index, layer_index, source_node_index, source_layer_index, source_layer_height
+            ),
+            ValidationError::SkipsPastInputLayer {
+                layer_index,
+                node_index,
+                source_node_index,
+                skipped_layers,
+            } => write!(
+                f,
+                "Node {} of layer {} has an input from node {} which skips {} layers, past the input layer",
+                node_index, layer_index, source_node_index, skipped_layers
+            ),
+            ValidationError::NonFiniteWeight {
+                layer_index,
+                node_index,
+                source_node_index,
+                weight,
+            } => write!(
+                f,
+                "Node {} of layer {} has a non-finite weight ({}) for its input from node {}",
+                node_index, layer_index, weight, source_node_index
+            ),
+            ValidationError::NonFiniteBias {
+                layer_index,
+                node_index,
+                bias,
+            } => write!(
+                f,
+                "Node {} of layer {} has a non-finite bias ({})",
+                node_index, layer_index, bias
+            ),
+            ValidationError::NonFiniteRecurrentWeight {
+                layer_index,
+                node_index,
+                weight,
+            } => write!(
+                f,
+                "Node {} of layer {} has a non-finite recurrent weight ({})",
+                node_index, layer_index, weight
+            ),
+            ValidationError::InputCountMismatch { expected, actual } => write!(
+                f,
+                "Expected the network to have {} inputs, it has {}",
+                expected, actual
+            ),
+            ValidationError::TooFewOutputs { expected, actual } => write!(
+                f,
+                "Expected the network to have at least {} outputs, it has {}",
+                expected, actual
+            ),
+            ValidationError::EvenKernelDiameter {
+                layer_index,
+                kernel_diameter,
+            } => write!(
+                f,
+                "Layer {} has an even kernel diameter ({}), so its kernels have no center",
+                layer_index, kernel_diameter
+            ),
+            ValidationError::ChannelCountMismatch {
+                layer_index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Layer {} takes {} input channels, but the previous layer outputs {}",
+                layer_index, actual, expected
+            ),
+            ValidationError::ParameterCountMismatch {
+                layer_index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Layer {} has {} weights and biases, it should have {}",
+                layer_index, actual, expected
+            ),
+        }
+    }
+}
+
+/// All of the problems found when validating a network, never empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationErrors(pub Vec<ValidationError>);
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The network is invalid:")?;
+
+        for error in &self.0 {
+            write!(f, "\n- {}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl ValidationErrors {
+    pub(crate) fn into_result(errors: Vec<ValidationError>) -> Result<(), Self> {
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Self(errors))
+        }
+    }
+}
+
+impl Network {
+    /// Checks the structure of the network, so that it can be computed without panicking.
+    /// Doesn't know what the network is used for, see `NetworkPlayerConfig::validate_network` for that.
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        ValidationErrors::into_result(self.find_validation_errors())
+    }
+
+    pub(crate) fn find_validation_errors(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        if self.compute_layers.is_empty() {
+            errors.push(ValidationError::NoComputeLayers);
+        }
+
+        for (comp_layer_index, comp_layer) in self.compute_layers.iter().enumerate() {
+            // The layer indices of the compute layers are offset by one due to the input layer.
+            let layer_index = comp_layer_index + 1;
+
+            for (node_index, node) in comp_layer.nodes.iter().enumerate() {
+                if !node.bias.is_finite() {
+                    errors.push(ValidationError::NonFiniteBias {
+                        layer_index,
+                        node_index,
+                        bias: node.bias,
+                    });
+                }
+
+                if let Some(weight) = node.recurrent_weight
+                    && !weight.is_finite()
+                {
+                    errors.push(ValidationError::NonFiniteRecurrentWeight {
+                        layer_index,
+                        node_index,
+                        weight,
+                    });
+                }
+
+                for input in &node.inputs {
+                    match input.source_layer_index(layer_index) {
+                        Some(source_layer_index) => {
+                            let source_layer_height = self
+                                .layer(source_layer_index)
+                                .map_or(0, |layer| layer.output_node_indices().len());
+
+                            if input.node_index >= source_layer_height {
+                                errors.push(ValidationError::InputOutOfBounds {
+                                    layer_index,
+                                    node_index,
+                                    source_layer_index,
+                                    source_node_index: input.node_index,
+                                    source_layer_height,
+                                });
+                            }
+                        }
+                        None => errors.push(ValidationError::SkipsPastInputLayer {
+                            layer_index,
+                            node_index,
+                            source_node_index: input.node_index,
+                            skipped_layers: input.skipped_layers,
+                        }),
+                    }
+
+                    if !input.weight.is_finite() {
+                        errors.push(ValidationError::NonFiniteWeight {
+                            layer_index,
+                            node_index,
+                            source_node_index: input.node_index,
+                            weight: input.weight,
+                        });
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+}
@@ -6,7 +6,10 @@ use libml::game::networksave::NetworkSave;
 
 fn main() {
     let path = env::args().skip(1).collect::<Vec<_>>().join(" ");
-    let network_save = NetworkSave::load(&path).expect("Couldn't load network save");
+    let (network_save, warnings) = NetworkSave::load(&path).expect("Couldn't load network save");
+    for warning in warnings {
+        eprintln!("Warning: {}", warning);
+    }
     
     let serialized_network = serde_json::to_string_pretty(&network_save.network).expect("Couldn't serialize network");
     
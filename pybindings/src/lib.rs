@@ -0,0 +1,462 @@
+//! Python bindings for the game and network libraries, built as the `ml_life_killer` extension module.
+//! Boards are exchanged with numpy as 2D uint8 arrays of shape (height, width), one meaning alive and zero dead.
+
+use std::ffi::CString;
+
+use libgame::{
+    Game,
+    board::{GameBoard, TileState},
+    rule::Rule,
+};
+use libml::{
+    game::{NetworkPlayer, NetworkPlayerConfig, kernel::ContextFeatures, networksave::NetworkSave},
+    network::Network,
+};
+use numpy::{PyArray2, PyReadonlyArray2, ndarray::Array2};
+use pyo3::{
+    exceptions::{PyIOError, PyUserWarning, PyValueError},
+    prelude::*,
+};
+
+#[pyclass(name = "Rule", module = "ml_life_killer")]
+#[derive(Clone)]
+struct PyRule {
+    inner: Rule,
+}
+
+#[pymethods]
+impl PyRule {
+    /// Creates a rule from the neighbor counts that cause births and survivals, defaulting to Conway's rules.
+    #[new]
+    #[pyo3(signature = (birth=None, survive=None))]
+    fn new(birth: Option<Vec<usize>>, survive: Option<Vec<usize>>) -> Self {
+        let default_rule = Rule::default();
+
+        Self {
+            inner: Rule {
+                birth: birth.unwrap_or(default_rule.birth),
+                survive: survive.unwrap_or(default_rule.survive),
+                noise: None,
+            },
+        }
+    }
+
+    #[staticmethod]
+    fn high_life() -> Self {
+        Self {
+            inner: Rule::high_life(),
+        }
+    }
+
+    #[getter]
+    fn birth(&self) -> Vec<usize> {
+        self.inner.birth.clone()
+    }
+
+    #[getter]
+    fn survive(&self) -> Vec<usize> {
+        self.inner.survive.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Rule(birth={:?}, survive={:?})",
+            self.inner.birth, self.inner.survive
+        )
+    }
+}
+
+#[pyclass(name = "GameBoard", module = "ml_life_killer")]
+#[derive(Clone)]
+struct PyGameBoard {
+    inner: GameBoard,
+}
+
+#[pymethods]
+impl PyGameBoard {
+    #[new]
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            inner: GameBoard::new(width, height),
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (width, height, alive_cells, block_size=1))]
+    fn random(
+        width: usize,
+        height: usize,
+        alive_cells: usize,
+        block_size: usize,
+    ) -> PyResult<Self> {
+        if block_size > width || block_size > height {
+            return Err(PyValueError::new_err(format!(
+                "Block size {} doesn't fit on a {}x{} board",
+                block_size, width, height
+            )));
+        }
+
+        // NOTE: Blocks are placed at distinct positions, so there can't be more of them than there are positions.
+        let block_positions = (width - block_size + 1) * (height - block_size + 1);
+        if alive_cells > block_positions {
+            return Err(PyValueError::new_err(format!(
+                "Too many alive cells, a {}x{} board only has room for {} blocks of size {}",
+                width, height, block_positions, block_size
+            )));
+        }
+
+        Ok(Self {
+            inner: GameBoard::new_random(width, height, alive_cells, block_size),
+        })
+    }
+
+    #[staticmethod]
+    fn from_numpy(tiles: PyReadonlyArray2<'_, u8>) -> Self {
+        let tiles = tiles.as_array();
+        let (height, width) = tiles.dim();
+
+        // NOTE: Iterating the array directly goes in logical (row-major) order regardless of the memory layout.
+        let tiles = tiles
+            .iter()
+            .map(|tile| {
+                if *tile != 0 {
+                    TileState::Alive
+                } else {
+                    TileState::Dead
+                }
+            })
+            .collect();
+
+        Self {
+            inner: GameBoard::with_tiles(width, height, tiles),
+        }
+    }
+
+    fn to_numpy<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<u8>>> {
+        let tiles = self
+            .inner
+            .tiles
+            .iter()
+            .map(|tile| (*tile == TileState::Alive) as u8)
+            .collect();
+
+        let array = Array2::from_shape_vec((self.inner.height, self.inner.width), tiles)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        Ok(PyArray2::from_owned_array(py, array))
+    }
+
+    #[getter]
+    fn width(&self) -> usize {
+        self.inner.width
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.inner.height
+    }
+
+    fn get(&self, x: usize, y: usize) -> PyResult<bool> {
+        let tile = self.inner.tile([x, y]).ok_or_else(|| out_of_bounds(x, y))?;
+        Ok(*tile == TileState::Alive)
+    }
+
+    fn set(&mut self, x: usize, y: usize, alive: bool) -> PyResult<()> {
+        let tile = self
+            .inner
+            .tile_mut([x, y])
+            .ok_or_else(|| out_of_bounds(x, y))?;
+        *tile = if alive {
+            TileState::Alive
+        } else {
+            TileState::Dead
+        };
+        Ok(())
+    }
+
+    /// The number of generations the tile has been in its current state, if ages are tracked.
+    fn age(&self, x: usize, y: usize) -> Option<usize> {
+        self.inner.tile_age([x, y])
+    }
+
+    fn track_ages(&mut self) {
+        self.inner.track_ages();
+    }
+}
+
+#[pyclass(name = "Game", module = "ml_life_killer")]
+struct PyGame {
+    inner: Game,
+}
+
+#[pymethods]
+impl PyGame {
+    #[new]
+    #[pyo3(signature = (board, rule=None, seed=None))]
+    fn new(board: PyGameBoard, rule: Option<PyRule>, seed: Option<u64>) -> Self {
+        let rule = rule.map(|rule| rule.inner).unwrap_or_default();
+
+        let inner = match seed {
+            Some(seed) => Game::with_seed(board.inner, rule, seed),
+            None => Game::new(board.inner, rule),
+        };
+
+        Self { inner }
+    }
+
+    #[pyo3(signature = (generations=1))]
+    fn tick(&mut self, generations: usize) {
+        for _ in 0..generations {
+            self.inner.tick();
+        }
+    }
+
+    fn count_alive(&self) -> usize {
+        self.inner.count_cells(TileState::Alive)
+    }
+
+    /// A copy of the board, changes to it won't affect the game unless it's assigned back.
+    #[getter]
+    fn board(&self) -> PyGameBoard {
+        PyGameBoard {
+            inner: self.inner.board.clone(),
+        }
+    }
+
+    #[setter]
+    fn set_board(&mut self, board: PyGameBoard) {
+        self.inner.board = board.inner;
+    }
+
+    #[getter]
+    fn rule(&self) -> PyRule {
+        PyRule {
+            inner: self.inner.rule.clone(),
+        }
+    }
+
+    #[setter]
+    fn set_rule(&mut self, rule: PyRule) {
+        self.inner.rule = rule.inner;
+    }
+}
+
+#[pyclass(name = "Network", module = "ml_life_killer")]
+#[derive(Clone)]
+struct PyNetwork {
+    inner: Network,
+}
+
+#[pymethods]
+impl PyNetwork {
+    /// Computes the outputs of the network for the given inputs.
+    fn compute(&self, inputs: Vec<f32>) -> PyResult<Vec<f32>> {
+        let input_count = self.input_count();
+        if inputs.len() > input_count {
+            return Err(PyValueError::new_err(format!(
+                "Too many inputs, the network only has {} input nodes",
+                input_count
+            )));
+        }
+
+        Ok(self.inner.compute(&inputs))
+    }
+
+    #[getter]
+    fn input_count(&self) -> usize {
+        self.inner.input_layer.height()
+    }
+
+    /// The number of nodes in each layer, starting from the input layer.
+    #[getter]
+    fn layer_heights(&self) -> Vec<usize> {
+        self.inner
+            .layers()
+            .map(|layer| layer.output_node_indices().len())
+            .collect()
+    }
+}
+
+/// Which features of the whole game a NetworkPlayer feeds its network along with each kernel.
+#[pyclass(name = "ContextFeatures", module = "ml_life_killer")]
+#[derive(Clone)]
+struct PyContextFeatures {
+    inner: ContextFeatures,
+}
+
+#[pymethods]
+impl PyContextFeatures {
+    #[new]
+    #[pyo3(signature = (population=false, rounds_left=false, coordinates=false, edge_distance=false, region_population_scales=0))]
+    fn new(
+        population: bool,
+        rounds_left: bool,
+        coordinates: bool,
+        edge_distance: bool,
+        region_population_scales: usize,
+    ) -> Self {
+        Self {
+            inner: ContextFeatures {
+                population,
+                rounds_left,
+                coordinates,
+                edge_distance,
+                region_population_scales,
+            },
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ContextFeatures(population={}, rounds_left={}, coordinates={}, edge_distance={}, region_population_scales={})",
+            py_bool(self.inner.population),
+            py_bool(self.inner.rounds_left),
+            py_bool(self.inner.coordinates),
+            py_bool(self.inner.edge_distance),
+            self.inner.region_population_scales
+        )
+    }
+}
+
+#[pyclass(name = "NetworkPlayer", module = "ml_life_killer")]
+struct PyNetworkPlayer {
+    inner: NetworkPlayer<'static>,
+}
+
+#[pymethods]
+impl PyNetworkPlayer {
+    /// Creates a player, failing if the network doesn't fit the configuration.
+    #[new]
+    #[pyo3(signature = (
+        network,
+        kernel_diameter,
+        use_tile_ages=false,
+        memory_channels=0,
+        context_features=None,
+        preview_ticks=0,
+        use_neighbour_counts=false,
+        history_depth=0,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        network: PyNetwork,
+        kernel_diameter: usize,
+        use_tile_ages: bool,
+        memory_channels: usize,
+        context_features: Option<PyContextFeatures>,
+        preview_ticks: usize,
+        use_neighbour_counts: bool,
+        history_depth: usize,
+    ) -> PyResult<Self> {
+        let config = NetworkPlayerConfig {
+            kernel_diameter,
+            use_kernel_cache: false,
+            use_tile_ages,
+            memory_channels,
+            context_features: context_features
+                .map(|context_features| context_features.inner)
+                .unwrap_or_default(),
+            preview_ticks,
+            use_neighbour_counts,
+            history_depth,
+        };
+
+        config
+            .validate_network(&network.inner)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        Ok(Self {
+            inner: NetworkPlayer::new(config, network.inner),
+        })
+    }
+
+    /// Lets the network make a move on the game, returning the (x, y, alive) of the changed tile if there was one.
+    fn play_step(&mut self, mut game: PyRefMut<'_, PyGame>) -> Option<(usize, usize, bool)> {
+        let network_move = self.inner.play_step(&mut game.inner)?;
+
+        Some((
+            network_move.position.x,
+            network_move.position.y,
+            network_move.new_state == TileState::Alive,
+        ))
+    }
+
+    /// Forgets everything the player remembers from earlier moves, which should be done before starting a new game.
+    fn reset_memory(&mut self) {
+        self.inner.reset_memory();
+    }
+
+    /// Tells the player how many of the rounds of the game are left, for networks which take it into account.
+    fn set_rounds_left(&mut self, rounds_left: usize, max_rounds: usize) {
+        self.inner.set_rounds_left(rounds_left, max_rounds);
+    }
+
+    #[getter]
+    fn kernel_diameter(&self) -> usize {
+        self.inner.config.kernel_diameter
+    }
+}
+
+#[pyclass(name = "NetworkSave", module = "ml_life_killer")]
+struct PyNetworkSave {
+    inner: NetworkSave,
+}
+
+#[pymethods]
+impl PyNetworkSave {
+    #[staticmethod]
+    fn load(py: Python<'_>, path: &str) -> PyResult<Self> {
+        let (inner, warnings) =
+            NetworkSave::load(path).map_err(|e| PyIOError::new_err(format!("{e:#}")))?;
+
+        for warning in warnings {
+            // Interior nul bytes can't be represented in a C string, so just drop them.
+            let message = CString::new(warning.to_string().replace('\0', "")).unwrap_or_default();
+            PyErr::warn(py, &py.get_type::<PyUserWarning>(), &message, 1)?;
+        }
+
+        Ok(Self { inner })
+    }
+
+    /// A copy of the saved network.
+    #[getter]
+    fn network(&self) -> PyNetwork {
+        PyNetwork {
+            inner: self.inner.network.clone(),
+        }
+    }
+
+    /// A player for the saved network, configured the way it was trained.
+    fn player(&self) -> PyNetworkPlayer {
+        PyNetworkPlayer {
+            inner: NetworkPlayer::new(self.inner.player_config, self.inner.network.clone()),
+        }
+    }
+
+    /// The saved network serialized as JSON, the same way netdump does.
+    fn network_json(&self) -> PyResult<String> {
+        serde_json::to_string_pretty(&self.inner.network)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
+
+/// Formats a bool the way Python writes it.
+fn py_bool(value: bool) -> &'static str {
+    if value { "True" } else { "False" }
+}
+
+fn out_of_bounds(x: usize, y: usize) -> PyErr {
+    PyValueError::new_err(format!("Position ({}, {}) is outside of the board", x, y))
+}
+
+#[pymodule]
+fn ml_life_killer(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyRule>()?;
+    module.add_class::<PyGameBoard>()?;
+    module.add_class::<PyGame>()?;
+    module.add_class::<PyNetwork>()?;
+    module.add_class::<PyContextFeatures>()?;
+    module.add_class::<PyNetworkPlayer>()?;
+    module.add_class::<PyNetworkSave>()?;
+    Ok(())
+}
@@ -1,5 +1,3 @@
-use libml::network::Network;
-
 pub mod game;
 
 pub trait TrainerAdapterFactory<T>
@@ -10,5 +8,8 @@ where
 }
 
 pub trait TrainerAdapter: Sync {
-    fn try_out(&self, network: &mut Network) -> isize;
+    /// The kind of network the adapter tries out.
+    type Network;
+
+    fn try_out(&self, network: &Self::Network) -> isize;
 }
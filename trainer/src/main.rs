@@ -3,24 +3,49 @@
 use std::{cmp::Ordering, collections::VecDeque, env, fs, path::{Path, PathBuf}, process::exit, time::Instant};
 
 use adapter::{
-    game::{GameTrainerAdapterConfig, GameTrainerAdapterFactory},
+    game::{ConvGameTrainerAdapterFactory, GameTrainerAdapterConfig, GameTrainerAdapterFactory},
     TrainerAdapter, TrainerAdapterFactory,
 };
 use colored::{ColoredString, Colorize};
+use libgame::{rule::Rule, topology::Topology};
 use libml::{
-    game::{networksave::NetworkSave, NetworkPlayerConfig},
-    network::{functions::{Activator, Combinator}, Network, NetworkConfig},
+    game::{
+        conv::ConvNetworkPlayerConfig,
+        kernel::ContextFeatures,
+        networksave::{ConvNetworkSave, NetworkSave},
+        NetworkPlayerConfig,
+    },
+    network::{
+        conv::ConvNetwork,
+        functions::{Activator, Combinator},
+        layer::conv::Padding,
+        ActivationPlacement, Network, NetworkConfig,
+    },
 };
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use trainer::{Trainer, TrainerConfig};
+use trainer::{Evolvable, Trainer, TrainerConfig};
 
 mod adapter;
 mod trainer;
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 struct Config {
     trainer_config: TrainerConfig, // Configuration for the training process.
     adapter_config: GameTrainerAdapterConfig, // Configuration for the games played during training.
+
+    #[serde(default)]
+    player_kind: PlayerKind, // Which player the networks are trained for.
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+enum PlayerKind {
+    /// NetworkPlayer, computing a network over the kernel of every tile.
+    #[default]
+    Kernel,
+
+    /// ConvNetworkPlayer, computing a convolutional network over the whole board.
+    Conv,
 }
 
 fn main() {
@@ -50,7 +75,12 @@ fn main() {
                 max_rounds: 128,
                 disable_nature: false,
                 evil: true,
+                rule: Rule::default(),
+                rule_map: None,
+                topology: Topology::default(),
+                track_ages: false,
             },
+            player_kind: PlayerKind::default(),
         };
 
         let default_config_path = Path::new("trainer_default_config.json");
@@ -67,28 +97,51 @@ fn main() {
         exit(0);
     };
 
-    let network_save = if let Some(network_save_path) = args.next() && network_save_path != "-" {
-        NetworkSave::load(network_save_path).expect("Couldn't load network save")
+    let network_save_path = args.next().filter(|network_save_path| network_save_path != "-");
+
+    match config.player_kind {
+        PlayerKind::Kernel => train_kernel_network(run_id, config, network_save_path),
+        PlayerKind::Conv => train_conv_network(run_id, config, network_save_path),
+    }
+}
+
+fn train_kernel_network(run_id: String, config: Config, network_save_path: Option<String>) {
+    let network_save = if let Some(network_save_path) = network_save_path {
+        let (network_save, warnings) =
+            NetworkSave::load(network_save_path).expect("Couldn't load network save");
+
+        for warning in warnings {
+            eprintln!("Warning: {}", warning);
+        }
+
+        network_save
     } else {
         // TODO: Ask network parameters from the user interactively
         let kernel_diameter: usize = 5;
 
+        let player_config = NetworkPlayerConfig {
+            kernel_diameter,
+            use_kernel_cache: false,
+            use_tile_ages: false,
+            memory_channels: 0,
+            context_features: ContextFeatures::default(),
+            preview_ticks: 0,
+            use_neighbour_counts: false,
+            history_depth: 0,
+        };
+
         let network = Network::new(
             NetworkConfig {
                 activator: Activator::ReLU,
                 combinator: Combinator::Mul,
+                activation_placement: ActivationPlacement::ActivateSums,
             },
-            kernel_diameter.pow(2), // Input layer height
-            3,                      // Hidden layer count
-            16,                     // Hidden layer height
-            2,                      // Output layer height
+            player_config.input_count(),  // Input layer height
+            0,                            // Hidden layer count, which the topology mutations grow as needed
+            0,                            // Hidden layer height
+            player_config.output_count(), // Output layer height
         );
 
-        let player_config = NetworkPlayerConfig {
-            kernel_diameter,
-            use_kernel_cache: false,
-        };
-
         NetworkSave {
             network,
             player_config,
@@ -102,23 +155,86 @@ fn main() {
 
     let trainer = Trainer::new(config.trainer_config, adapter_factory);
 
-    run_training(
-        run_id,
-        trainer,
-        network_save.player_config,
-        network_save.network,
-    );
+    let mut network = network_save.network;
+
+    // Older saves don't have innovation numbers, which the structural mutations need to line up.
+    network.assign_innovations();
+
+    run_training(run_id, trainer, network, |network, path| {
+        NetworkSave {
+            network: network.clone(),
+            player_config: network_save.player_config,
+        }
+        .save(path)
+        .expect("Couldn't save network");
+    });
+}
+
+fn train_conv_network(run_id: String, config: Config, network_save_path: Option<String>) {
+    let network_save = if let Some(network_save_path) = network_save_path {
+        ConvNetworkSave::load(network_save_path).expect("Couldn't load network save")
+    } else {
+        let player_config = ConvNetworkPlayerConfig {
+            use_tile_ages: false,
+        };
+
+        // The network should see the edges of the board the way the game does.
+        let padding = match config.adapter_config.topology {
+            Topology::Bounded => Padding::Zeros,
+            Topology::Toroidal => Padding::Wrap,
+        };
+
+        let mut network = ConvNetwork::new(
+            Activator::Tanh,
+            3, // Kernel diameter
+            padding,
+            player_config.input_channels(),
+            2, // Hidden layer count, which mutations don't change
+            8, // Hidden layer channels
+            player_config.output_channels(),
+        );
+
+        // Every weight is used from the start, unlike the inputs of kernel networks which mutations add one by one,
+        // so they start out random for the channels of a layer not to all learn the same thing.
+        let rng = &mut rand::rng();
+        for parameter in network.layers.iter_mut().flat_map(|layer| layer.parameters_mut()) {
+            *parameter = rng.random_range(-0.5..0.5);
+        }
+
+        ConvNetworkSave {
+            network,
+            player_config,
+        }
+    };
+
+    let adapter_factory = ConvGameTrainerAdapterFactory {
+        config: config.adapter_config,
+        player_config: network_save.player_config,
+    };
+
+    let trainer = Trainer::new(config.trainer_config, adapter_factory);
+
+    run_training(run_id, trainer, network_save.network, |network, path| {
+        ConvNetworkSave {
+            network: network.clone(),
+            player_config: network_save.player_config,
+        }
+        .save(path)
+        .expect("Couldn't save network");
+    });
 }
 
-fn run_training<A, AF>(
+fn run_training<A, AF, S>(
     run_id: String,
     trainer: Trainer<A, AF>,
 
-    network_player_config: NetworkPlayerConfig,
-    mut network: Network,
+    mut network: A::Network,
+    save_network: S,
 ) where
     A: TrainerAdapter,
+    A::Network: Evolvable,
     AF: TrainerAdapterFactory<A>,
+    S: Fn(&A::Network, PathBuf),
 {
     let mut score = ValueThingy::new(50);
     let mut last_save_avg_score = None;
@@ -190,12 +306,7 @@ fn run_training<A, AF>(
         if improved || current_instant.duration_since(last_save_instant).as_secs() > 60 {
             let path = PathBuf::from("networks").join(format!("{}_gen{}.json", run_id, generation));
 
-            NetworkSave {
-                network: network.clone(),
-                player_config: network_player_config,
-            }
-            .save(path)
-            .expect("Couldn't save network");
+            save_network(&network, path);
 
             last_save_avg_score = Some(avg_score);
             last_save_instant = current_instant;
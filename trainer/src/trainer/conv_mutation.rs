@@ -0,0 +1,65 @@
+use std::iter;
+
+use libml::network::{conv::ConvNetwork, functions::Activator};
+use rand::{seq::{IndexedMutRandom, IteratorRandom}, Rng};
+use strum::IntoEnumIterator;
+
+#[derive(Debug)]
+pub enum ConvMutation<'a> {
+    AdjustParameter {
+        parameter: &'a mut f32,
+        adjustment: f32,
+    },
+    SwitchActivator {
+        activator: &'a mut Option<Activator>,
+        new_activator: Option<Activator>,
+    },
+}
+
+pub fn weight_adjustment(network: &mut ConvNetwork) -> Option<ConvMutation<'_>> {
+    let rng = &mut rand::rng();
+
+    let layer = network.layers.choose_mut(rng)?;
+    let weight = layer.weights_mut().choose(rng)?;
+
+    let adjustment_max_magnitude = (weight.abs() / 2.0).max(0.01);
+    let adjustment = rng.random_range(-adjustment_max_magnitude..adjustment_max_magnitude);
+
+    Some(ConvMutation::AdjustParameter {
+        parameter: weight,
+        adjustment,
+    })
+}
+
+pub fn bias_adjustment(network: &mut ConvNetwork) -> Option<ConvMutation<'_>> {
+    let rng = &mut rand::rng();
+
+    let layer = network.layers.choose_mut(rng)?;
+    let bias = layer.biases_mut().choose(rng)?;
+
+    // Biases start out at zero, so allow for bigger steps than with weights to get them going.
+    let adjustment_max_magnitude = (bias.abs() / 2.0).max(0.1);
+    let adjustment = rng.random_range(-adjustment_max_magnitude..adjustment_max_magnitude);
+
+    Some(ConvMutation::AdjustParameter {
+        parameter: bias,
+        adjustment,
+    })
+}
+
+/// Switches the activator of a layer, None making it go back to the activator of the network.
+pub fn activator_switch(network: &mut ConvNetwork) -> Option<ConvMutation<'_>> {
+    let rng = &mut rand::rng();
+
+    let activator = &mut network.layers.choose_mut(rng)?.activator;
+
+    let new_activator = iter::once(None)
+        .chain(Activator::iter().map(Some))
+        .filter(|new_activator| new_activator != activator)
+        .choose(rng)?;
+
+    Some(ConvMutation::SwitchActivator {
+        activator,
+        new_activator,
+    })
+}
@@ -1,9 +1,12 @@
 use std::{iter, marker::PhantomData};
 
 use itertools::Itertools;
+use conv_mutation::ConvMutation;
 use libml::network::{
     Network,
-    node::NodeInput,
+    conv::ConvNetwork,
+    innovation::{InnovationTracker, StructuralMutation},
+    node::{Node, NodeInput},
 };
 use mutation::Mutation;
 use rand::{
@@ -15,6 +18,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::adapter::{TrainerAdapter, TrainerAdapterFactory};
 
+mod conv_mutation;
 mod mutation;
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize)]
@@ -32,6 +36,20 @@ pub struct TrainerConfig {
     pub generation_iterations: usize,
 }
 
+/// A kind of network which the trainer can evolve.
+pub trait Evolvable: Clone + Send {
+    /// Shared by the mutations of every contender of a generation.
+    type GenerationState;
+
+    fn start_generation(&self) -> Self::GenerationState;
+
+    /// Applies a single round of mutations.
+    fn mutated(self, generation_state: &mut Self::GenerationState) -> Self;
+
+    /// Called on the best contender of a generation, which the next generation is based on.
+    fn finish_generation(&mut self, generation_state: Self::GenerationState);
+}
+
 pub struct Trainer<A, AF>
 where
     A: TrainerAdapter,
@@ -60,11 +78,14 @@ where
 impl<A, AF> Trainer<A, AF>
 where
     A: TrainerAdapter,
+    A::Network: Evolvable,
     AF: TrainerAdapterFactory<A>,
 {
     /// A generation consists of a single set of mutated networks based on the previous network,
     /// of which the best average-performers are selected.
-    pub fn train_generation(&self, network: Network) -> (Network, isize) {
+    pub fn train_generation(&self, network: A::Network) -> (A::Network, isize) {
+        let mut generation_state = network.start_generation();
+
         // NOTE: -1 because we chain the original network.
         let contenders = iter::repeat_n(network.clone(), self.config.generation_contenders - 1)
             .map(|mut new_contender| {
@@ -74,7 +95,7 @@ where
 
                 let mutation_count = (self.config.generation_mutations as i32 + mutation_count_randomization).max(1) as usize;
                 for _ in 0..mutation_count {
-                    new_contender = self.mutate(new_contender);
+                    new_contender = new_contender.mutated(&mut generation_state);
                 }
 
                 new_contender
@@ -109,20 +130,43 @@ where
 
         // SAFETY: There's always going to be atleast one contender (due to including the original network),
         //         so unwrap should always be OK.
-        scored_contenders.max_by_key(|(_, score)| *score).unwrap()
+        let (mut best_contender, best_score) =
+            scored_contenders.max_by_key(|(_, score)| *score).unwrap();
+
+        best_contender.finish_generation(generation_state);
+        (best_contender, best_score)
     }
+}
+
+impl Evolvable for Network {
+    /// Contenders making the same structural mutation get the same innovation numbers for it.
+    type GenerationState = InnovationTracker;
+
+    fn start_generation(&self) -> InnovationTracker {
+        InnovationTracker::new(self)
+    }
+
+    fn mutated(self, innovations: &mut InnovationTracker) -> Self {
+        let mut network = self;
 
-    fn mutate(&self, mut network: Network) -> Network {
         let preferred_mutation_providers = {
-            let preferred_mutation_type_choice = [(0, 7), (1, 1), (2, 2)]
+            let preferred_mutation_type_choice = [(0, 7), (1, 1), (2, 2), (3, 2), (4, 1), (5, 1), (6, 1), (7, 1)]
                 .choose_weighted(&mut rand::rng(), |(_, weight)| *weight)
                 .map(|(choice, _)| *choice)
                 .unwrap();
 
             match preferred_mutation_type_choice {
-                0 => vec![mutation::weight_adjustment, mutation::input_creation, mutation::input_deletion],
-                1 => vec![mutation::input_creation, mutation::weight_adjustment, mutation::input_deletion],
-                2 => vec![mutation::input_deletion, mutation::weight_adjustment, mutation::input_creation],
+                0 => vec![mutation::weight_adjustment, mutation::input_creation, mutation::input_deletion, mutation::bias_adjustment],
+                1 => vec![mutation::input_creation, mutation::weight_adjustment, mutation::input_deletion, mutation::bias_adjustment],
+                2 => vec![mutation::input_deletion, mutation::weight_adjustment, mutation::input_creation, mutation::bias_adjustment],
+                3 => vec![mutation::bias_adjustment, mutation::weight_adjustment, mutation::input_creation, mutation::input_deletion],
+                // Switching functions is quite disruptive, so it's only done when preferred.
+                4 => vec![mutation::function_switch, mutation::weight_adjustment, mutation::input_creation, mutation::input_deletion, mutation::bias_adjustment],
+                // Same goes for changing the structure of the network.
+                5 => vec![mutation::input_split, mutation::weight_adjustment, mutation::input_creation, mutation::input_deletion, mutation::bias_adjustment],
+                6 => vec![mutation::topology_change, mutation::input_creation, mutation::weight_adjustment, mutation::input_deletion, mutation::bias_adjustment],
+                // As does making nodes remember their outputs between moves.
+                7 => vec![mutation::recurrence_adjustment, mutation::weight_adjustment, mutation::input_creation, mutation::input_deletion, mutation::bias_adjustment],
                 _ => unreachable!(),
             }
         };
@@ -136,22 +180,142 @@ where
                 Mutation::AdjustWeight { input, adjustment } => {
                     input.weight += adjustment;
                 }
+                Mutation::AdjustBias { node, adjustment } => {
+                    node.bias += adjustment;
+                }
                 Mutation::InputCreation {
                     node,
                     src_node_index,
+                    skipped_layers,
+                    source,
                     weight,
                 } => {
+                    let innovation = innovations.mutation_innovations(
+                        StructuralMutation::InputCreation {
+                            source,
+                            target: node.innovation,
+                        },
+                        1,
+                    );
+
                     node.inputs.push(NodeInput {
                         node_index: src_node_index,
                         weight,
+                        skipped_layers,
+                        innovation,
                     });
                 }
                 Mutation::InputDeletion { node, input_index } => {
                     node.inputs.remove(input_index);
                 }
+                Mutation::SwitchActivator {
+                    activator,
+                    new_activator,
+                } => {
+                    *activator = new_activator;
+                }
+                Mutation::SwitchCombinator {
+                    combinator,
+                    new_combinator,
+                } => {
+                    *combinator = new_combinator;
+                }
+                Mutation::AdjustRecurrence {
+                    recurrent_weight,
+                    new_recurrent_weight,
+                } => {
+                    *recurrent_weight = new_recurrent_weight;
+                }
+                Mutation::InputSplit {
+                    comp_layer_index,
+                    node_index,
+                    input_index,
+                } => {
+                    let input = &network.compute_layers[comp_layer_index].nodes[node_index].inputs
+                        [input_index];
+                    let innovation = innovations.mutation_innovations(
+                        StructuralMutation::InputSplit {
+                            input: input.innovation,
+                        },
+                        3,
+                    );
+
+                    network.split_input(comp_layer_index, node_index, input_index, innovation);
+                }
+                Mutation::NodeCreation { comp_layer_index } => {
+                    network.compute_layers[comp_layer_index].nodes.push(Node {
+                        innovation: innovations.unique_innovations(1),
+                        ..Node::default()
+                    });
+                }
+                Mutation::NodeDeletion {
+                    comp_layer_index,
+                    node_index,
+                } => {
+                    network.remove_node(comp_layer_index, node_index);
+                }
+                Mutation::LayerCreation { comp_layer_index } => {
+                    network.insert_layer(comp_layer_index);
+                }
+                Mutation::LayerDeletion { comp_layer_index } => {
+                    network.remove_layer(comp_layer_index);
+                }
             }
         }
 
         network
     }
+
+    fn finish_generation(&mut self, innovations: InnovationTracker) {
+        innovations.update_network(self);
+    }
+}
+
+impl Evolvable for ConvNetwork {
+    /// The structure of convolutional networks doesn't change, so there are no innovations to keep track of.
+    type GenerationState = ();
+
+    fn start_generation(&self) {}
+
+    fn mutated(mut self, _: &mut ()) -> Self {
+        let preferred_mutation_providers = {
+            let preferred_mutation_type_choice = [(0, 7), (1, 3), (2, 1)]
+                .choose_weighted(&mut rand::rng(), |(_, weight)| *weight)
+                .map(|(choice, _)| *choice)
+                .unwrap();
+
+            match preferred_mutation_type_choice {
+                0 => vec![conv_mutation::weight_adjustment, conv_mutation::bias_adjustment],
+                1 => vec![conv_mutation::bias_adjustment, conv_mutation::weight_adjustment],
+                // Switching functions is quite disruptive, so it's only done when preferred.
+                2 => vec![conv_mutation::activator_switch, conv_mutation::weight_adjustment, conv_mutation::bias_adjustment],
+                _ => unreachable!(),
+            }
+        };
+
+        for mutation_provider in preferred_mutation_providers.into_iter() {
+            let Some(mutation) = mutation_provider(&mut self) else {
+                continue;
+            };
+
+            match mutation {
+                ConvMutation::AdjustParameter {
+                    parameter,
+                    adjustment,
+                } => {
+                    *parameter += adjustment;
+                }
+                ConvMutation::SwitchActivator {
+                    activator,
+                    new_activator,
+                } => {
+                    *activator = new_activator;
+                }
+            }
+        }
+
+        self
+    }
+
+    fn finish_generation(&mut self, _: ()) {}
 }
@@ -1,5 +1,8 @@
-use libml::network::{node::{Node, NodeInput}, Network};
+use std::iter;
+
+use libml::network::{functions::{Activator, Combinator}, innovation::NodeKey, node::{Node, NodeInput}, Network};
 use rand::{seq::{IndexedMutRandom, IteratorRandom}, Rng};
+use strum::IntoEnumIterator;
 
 #[derive(Debug)]
 pub enum Mutation<'a> {
@@ -7,18 +10,57 @@ pub enum Mutation<'a> {
         input: &'a mut NodeInput,
         adjustment: f32,
     },
+    AdjustBias {
+        node: &'a mut Node,
+        adjustment: f32,
+    },
     InputCreation {
         node: &'a mut Node,
         src_node_index: usize,
+        skipped_layers: usize,
+        source: NodeKey,
         weight: f32,
     },
     InputDeletion {
         node: &'a mut Node,
         input_index: usize,
     },
+    SwitchActivator {
+        activator: &'a mut Option<Activator>,
+        new_activator: Option<Activator>,
+    },
+    SwitchCombinator {
+        combinator: &'a mut Option<Combinator>,
+        new_combinator: Option<Combinator>,
+    },
+    AdjustRecurrence {
+        recurrent_weight: &'a mut Option<f32>,
+        new_recurrent_weight: Option<f32>,
+    },
+
+    // NOTE: Structural mutations change the layout of the whole network, so they're applied through the network
+    //       with compute layer indices instead of holding references into it.
+    InputSplit {
+        comp_layer_index: usize,
+        node_index: usize,
+        input_index: usize,
+    },
+    NodeCreation {
+        comp_layer_index: usize,
+    },
+    NodeDeletion {
+        comp_layer_index: usize,
+        node_index: usize,
+    },
+    LayerCreation {
+        comp_layer_index: usize,
+    },
+    LayerDeletion {
+        comp_layer_index: usize,
+    },
 }
 
-pub fn weight_adjustment(network: &mut Network) -> Option<Mutation> {
+pub fn weight_adjustment(network: &mut Network) -> Option<Mutation<'_>> {
     let rng = &mut rand::rng();
 
     let comp_layer = network.compute_layers.choose_mut(rng)?;
@@ -31,20 +73,37 @@ pub fn weight_adjustment(network: &mut Network) -> Option<Mutation> {
     Some(Mutation::AdjustWeight { input, adjustment })
 }
 
-pub fn input_creation(network: &mut Network) -> Option<Mutation> {
+pub fn bias_adjustment(network: &mut Network) -> Option<Mutation<'_>> {
+    let rng = &mut rand::rng();
+
+    let comp_layer = network.compute_layers.choose_mut(rng)?;
+    let node = comp_layer.nodes.iter_mut().choose(rng)?;
+
+    // Biases start out at zero, so allow for bigger steps than with weights to get them going.
+    let adjustment_max_magnitude = (node.bias.abs() / 2.0).max(0.1);
+    let adjustment = rng.random_range(-adjustment_max_magnitude..adjustment_max_magnitude);
+
+    Some(Mutation::AdjustBias { node, adjustment })
+}
+
+pub fn input_creation(network: &mut Network) -> Option<Mutation<'_>> {
     let rng = &mut rand::rng();
 
     let comp_layer_count = network.compute_layers.len();
     let comp_layer_index =
         (comp_layer_count > 0).then(|| rng.random_range(0..comp_layer_count))?;
 
+    // The source can be in any earlier layer, skipping the layers in between.
+    // NOTE: comp_layer_index is already the previous layer index here since we're going
+    //       from a compute layer index (doesn't include input layer!) to a general layer index.
+    let src_layer_index = rng.random_range(0..=comp_layer_index);
+    let skipped_layers = comp_layer_index - src_layer_index;
+
     let src_node_index = {
-        // NOTE: comp_layer_index is already the previous layer index here since we're going
-        //       from a compute layer index (doesn't include input layer!) to a general layer index.
-        let prev_layer_index = comp_layer_index;
-        let prev_layer_node_indices = network.layer(prev_layer_index)?.output_node_indices();
-        prev_layer_node_indices.into_iter().choose(rng)?
+        let src_layer_node_indices = network.layer(src_layer_index)?.output_node_indices();
+        src_layer_node_indices.into_iter().choose(rng)?
     };
+    let source = network.node_key(src_layer_index, src_node_index)?;
 
     let node = {
         let layer = network.compute_layers.get_mut(comp_layer_index)?;
@@ -55,7 +114,9 @@ pub fn input_creation(network: &mut Network) -> Option<Mutation> {
     if node
         .inputs
         .iter()
-        .any(|input| input.node_index == src_node_index)
+        .any(|input| {
+            input.node_index == src_node_index && input.skipped_layers == skipped_layers
+        })
     {
         return None;
     }
@@ -65,11 +126,13 @@ pub fn input_creation(network: &mut Network) -> Option<Mutation> {
     Some(Mutation::InputCreation {
         node,
         src_node_index,
+        skipped_layers,
+        source,
         weight,
     })
 }
 
-pub fn input_deletion(network: &mut Network) -> Option<Mutation> {
+pub fn input_deletion(network: &mut Network) -> Option<Mutation<'_>> {
     let rng = &mut rand::rng();
 
     let comp_layer = network.compute_layers.choose_mut(rng)?;
@@ -82,3 +145,128 @@ pub fn input_deletion(network: &mut Network) -> Option<Mutation> {
 
     Some(Mutation::InputDeletion { node, input_index })
 }
+
+pub fn function_switch(network: &mut Network) -> Option<Mutation<'_>> {
+    let rng = &mut rand::rng();
+
+    let comp_layer = network.compute_layers.choose_mut(rng)?;
+
+    // Switch the function of either the whole layer or just a single node.
+    let (activator, combinator) = if rng.random_bool(0.5) {
+        (&mut comp_layer.activator, &mut comp_layer.combinator)
+    } else {
+        let node = comp_layer.nodes.iter_mut().choose(rng)?;
+        (&mut node.activator, &mut node.combinator)
+    };
+
+    // None is also a valid choice, making the layer or node go back to the function of it's parent.
+    if rng.random_bool(0.5) {
+        let new_activator = iter::once(None)
+            .chain(Activator::iter().map(Some))
+            .filter(|new_activator| new_activator != activator)
+            .choose(rng)?;
+
+        Some(Mutation::SwitchActivator {
+            activator,
+            new_activator,
+        })
+    } else {
+        let new_combinator = iter::once(None)
+            .chain(Combinator::iter().map(Some))
+            .filter(|new_combinator| new_combinator != combinator)
+            .choose(rng)?;
+
+        Some(Mutation::SwitchCombinator {
+            combinator,
+            new_combinator,
+        })
+    }
+}
+
+/// Gives a node a recurrent weight, or adjusts or removes the one it has, letting it remember its output between moves.
+pub fn recurrence_adjustment(network: &mut Network) -> Option<Mutation<'_>> {
+    let rng = &mut rand::rng();
+
+    let comp_layer = network.compute_layers.choose_mut(rng)?;
+    let node = comp_layer.nodes.iter_mut().choose(rng)?;
+
+    let new_recurrent_weight = match node.recurrent_weight {
+        None => Some(rng.random_range(-1.0..=1.0)),
+        Some(_) if rng.random_bool(0.25) => None,
+        Some(recurrent_weight) => {
+            let adjustment_max_magnitude = (recurrent_weight.abs() / 2.0).max(0.01);
+            Some(recurrent_weight + rng.random_range(-adjustment_max_magnitude..adjustment_max_magnitude))
+        }
+    };
+
+    Some(Mutation::AdjustRecurrence {
+        recurrent_weight: &mut node.recurrent_weight,
+        new_recurrent_weight,
+    })
+}
+
+/// Inserts a node into an input, which is how NEAT grows networks without changing what they compute.
+pub fn input_split(network: &mut Network) -> Option<Mutation<'_>> {
+    let rng = &mut rand::rng();
+
+    let (comp_layer_index, comp_layer) = network.compute_layers.iter().enumerate().choose(rng)?;
+    let (node_index, node) = comp_layer.nodes.iter().enumerate().choose(rng)?;
+
+
+    let input_index = {
+        let input_count = node.inputs.len();
+        (input_count > 0).then(|| rng.random_range(0..input_count))?
+    };
+
+    Some(Mutation::InputSplit {
+        comp_layer_index,
+        node_index,
+        input_index,
+    })
+}
+
+/// Adds or removes a hidden node or layer, letting evolution find the size of the network.
+pub fn topology_change(network: &mut Network) -> Option<Mutation<'_>> {
+    let rng = &mut rand::rng();
+
+    // The output layer is always the last one and isn't changed.
+    let hidden_layer_count = network.compute_layers.len().checked_sub(1)?;
+
+    match rng.random_range(0..4) {
+        // Nodes are created without inputs, which input creation can then add.
+        0 => {
+            let comp_layer_index =
+                (hidden_layer_count > 0).then(|| rng.random_range(0..hidden_layer_count))?;
+
+            Some(Mutation::NodeCreation { comp_layer_index })
+        }
+        1 => {
+            let (comp_layer_index, comp_layer) = network.compute_layers[..hidden_layer_count]
+                .iter()
+                .enumerate()
+                .choose(rng)?;
+
+            let node_index = {
+                let node_count = comp_layer.nodes.len();
+                (node_count > 0).then(|| rng.random_range(0..node_count))?
+            };
+
+            Some(Mutation::NodeDeletion {
+                comp_layer_index,
+                node_index,
+            })
+        }
+        // Layers are created empty, which node creation and input splits can then fill.
+        2 => {
+            let comp_layer_index = rng.random_range(0..=hidden_layer_count);
+            Some(Mutation::LayerCreation { comp_layer_index })
+        }
+        3 => {
+            let comp_layer_index =
+                (hidden_layer_count > 0).then(|| rng.random_range(0..hidden_layer_count))?;
+
+            Some(Mutation::LayerDeletion { comp_layer_index })
+        }
+        _ => unreachable!(),
+    }
+}